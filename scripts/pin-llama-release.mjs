@@ -0,0 +1,35 @@
+#!/usr/bin/env node
+// Fija la versión de llama-server que instala la app de escritorio.
+// Uso: node ./scripts/pin-llama-release.mjs [tag]   (p. ej. b4600)
+// Descarga cada archivo de src-tauri/llama/manifest.json, calcula su sha256 y
+// reescribe el manifiesto. Con [tag] cambia antes la versión en todas las URLs.
+import fs from 'fs';
+import path from 'path';
+import crypto from 'crypto';
+
+const manifestPath = path.join(process.cwd(), 'src-tauri', 'llama', 'manifest.json');
+const manifest = JSON.parse(fs.readFileSync(manifestPath, 'utf8'));
+const tag = process.argv[2];
+
+if (tag && tag !== manifest.version) {
+  for (const asset of manifest.assets) {
+    asset.url = asset.url.split(manifest.version).join(tag);
+    asset.sha256 = '';
+  }
+  manifest.version = tag;
+}
+
+for (const asset of manifest.assets) {
+  console.log(`[pin-llama] descargando ${asset.url}`);
+  const res = await fetch(asset.url);
+  if (!res.ok) {
+    console.error(`[pin-llama] HTTP ${res.status} para ${asset.url}`);
+    process.exit(1);
+  }
+  const bytes = Buffer.from(await res.arrayBuffer());
+  asset.sha256 = crypto.createHash('sha256').update(bytes).digest('hex');
+  console.log(`[pin-llama] ${asset.os}/${asset.arch} ${asset.sha256} (${bytes.length} bytes)`);
+}
+
+fs.writeFileSync(manifestPath, JSON.stringify(manifest, null, 2) + '\n');
+console.log(`[pin-llama] ${manifestPath} actualizado a ${manifest.version}`);
@@ -0,0 +1,54 @@
+#!/usr/bin/env node
+// Firma el catálogo de modelos (Ed25519) que verifica la app de escritorio.
+// Uso: node ./scripts/sign-model-catalog.mjs <catalog.json> <clave-privada.pem> [--pin]
+// Escribe <catalog.json>.sig con la firma en hex sobre los bytes exactos del archivo.
+// Con --pin descarga antes cada modelo sin sha256 y completa sha256 y size_bytes.
+// La copia empaquetada (src-tauri/catalog/catalog.json) se firma igual: es el
+// catálogo del primer arranque sin conexión.
+import fs from 'fs';
+import crypto from 'crypto';
+import { Readable } from 'stream';
+
+const args = process.argv.slice(2);
+const pin = args.includes('--pin');
+const [catalogPath, keyPath] = args.filter((a) => a !== '--pin');
+if (!catalogPath || !keyPath) {
+  console.error('Uso: node ./scripts/sign-model-catalog.mjs <catalog.json> <clave-privada.pem> [--pin]');
+  process.exit(1);
+}
+
+if (pin) {
+  const catalog = JSON.parse(fs.readFileSync(catalogPath, 'utf8'));
+  for (const model of catalog.models) {
+    if (model.sha256) continue;
+    console.log(`[sign-catalog] descargando ${model.url}`);
+    const res = await fetch(model.url);
+    if (!res.ok) {
+      console.error(`[sign-catalog] HTTP ${res.status} para ${model.url}`);
+      process.exit(1);
+    }
+    // Los GGUF pesan gigas: se hashean en streaming
+    const hash = crypto.createHash('sha256');
+    let size = 0;
+    for await (const chunk of Readable.fromWeb(res.body)) {
+      hash.update(chunk);
+      size += chunk.length;
+    }
+    model.sha256 = hash.digest('hex');
+    model.size_bytes = size;
+    console.log(`[sign-catalog] ${model.id} ${model.sha256} (${size} bytes)`);
+  }
+  fs.writeFileSync(catalogPath, JSON.stringify(catalog, null, 2) + '\n');
+}
+
+const bytes = fs.readFileSync(catalogPath);
+// Validar que sea JSON antes de firmar
+JSON.parse(bytes.toString('utf8'));
+
+const key = crypto.createPrivateKey(fs.readFileSync(keyPath));
+const signature = crypto.sign(null, bytes, key).toString('hex');
+fs.writeFileSync(`${catalogPath}.sig`, signature + '\n');
+
+const publicKey = crypto.createPublicKey(key).export({ format: 'der', type: 'spki' });
+console.log(`[sign-catalog] ${catalogPath}.sig escrito`);
+console.log(`[sign-catalog] clave pública (hex): ${publicKey.subarray(-32).toString('hex')}`);
@@ -27,7 +27,21 @@ fn ensure_min_icon() {
   }
 }
 
+// Un build de release no puede salir con archivos sin sha256: la app se negaría
+// a instalarlos (ver llama_binary::pinned_asset y CatalogModel::pinned)
+fn check_pinned(file: &str, script: &str) {
+  println!("cargo:rerun-if-changed={}", file);
+  if env::var("PROFILE").as_deref() != Ok("release") { return; }
+  let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
+  let text = std::fs::read_to_string(PathBuf::from(manifest_dir).join(file)).unwrap_or_default();
+  if text.contains("\"sha256\": \"\"") {
+    panic!("{} has entries without sha256; run `node {}` before a release build", file, script);
+  }
+}
+
 fn main() {
   ensure_min_icon();
+  check_pinned("llama/manifest.json", "scripts/pin-llama-release.mjs");
+  check_pinned("catalog/catalog.json", "scripts/sign-model-catalog.mjs src-tauri/catalog/catalog.json <clave.pem> --pin");
   tauri_build::build();
 } 
\ No newline at end of file
@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::downloads;
+use crate::gguf;
+use crate::models;
+use crate::sideload;
+use crate::storage;
+
+// Adaptadores LoRA (vocabulario ganadero: razas, palpación, unidades) que se
+// aplican sobre un modelo base en vez de distribuir otro GGUF completo. Viven en
+// app_data/adapters como <id>.gguf + <id>.json y cada uno queda atado al sha256
+// del modelo base con el que se entrenó.
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct AdapterInfo {
+  pub id: String,
+  pub name: String,
+  pub path: String,
+  pub sha256: String,
+  pub size: u64,
+  pub base_sha256: String,
+  pub base_model: Option<String>,
+  pub architecture: Option<String>,
+  pub installed_at: u64,
+}
+
+fn now_secs() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn adapters_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  Ok(crate::app_data_dir(app).ok_or("app_data_dir not found")?.join("adapters"))
+}
+
+fn adapter_id(path: &Path) -> String {
+  let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("adapter");
+  let clean: String = stem.to_lowercase().chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '-' })
+    .collect();
+  clean.trim_matches('-').to_string()
+}
+
+fn valid_id(id: &str) -> bool {
+  !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+pub fn list(app: &tauri::AppHandle) -> Vec<AdapterInfo> {
+  let Ok(dir) = adapters_dir(app) else { return vec![] };
+  let Ok(rd) = std::fs::read_dir(&dir) else { return vec![] };
+  let mut found: Vec<AdapterInfo> = rd.flatten()
+    .map(|e| e.path())
+    .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+    .filter_map(|p| std::fs::read(p).ok().and_then(|b| serde_json::from_slice::<AdapterInfo>(&b).ok()))
+    // El .json sin su .gguf (borrado a mano) no cuenta
+    .filter(|a| Path::new(&a.path).is_file())
+    .collect();
+  found.sort_by(|a, b| a.id.cmp(&b.id));
+  found
+}
+
+// Adaptadores entrenados sobre el modelo con ese sha256
+pub fn for_base(app: &tauri::AppHandle, base_sha256: &str) -> Vec<AdapterInfo> {
+  list(app).into_iter().filter(|a| a.base_sha256 == base_sha256).collect()
+}
+
+// Modelo instalado cuyo sha256 es `digest` (calcula los sidecars que falten)
+fn find_base(app: &tauri::AppHandle, digest: &str) -> Option<gguf::GgufInfo> {
+  models::scan_models(app).into_iter()
+    .find(|info| downloads::model_digest(Path::new(&info.path)).map(|d| d == digest).unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn list_adapters(app: tauri::AppHandle) -> Vec<AdapterInfo> {
+  list(&app)
+}
+
+// Instala un adaptador LoRA en GGUF desde `path`. `base_sha256` es el modelo base
+// con el que se entrenó: debe estar instalado y ser de la misma arquitectura.
+#[tauri::command]
+pub async fn install_adapter(path: String, base_sha256: String, sha256: Option<String>, name: Option<String>, app: tauri::AppHandle) -> Result<AdapterInfo, String> {
+  let src = PathBuf::from(&path);
+  if !src.is_file() { return Err(format!("adapter file not found: {}", path)); }
+  let check = src.clone();
+  let info = tauri::async_runtime::spawn_blocking(move || gguf::inspect(&check)).await.map_err(|e| e.to_string())??;
+  if info.adapter_type.as_deref() != Some("lora") {
+    return Err(format!("{} no es un adaptador LoRA en GGUF", src.display()));
+  }
+
+  let base_sha256 = base_sha256.trim().to_lowercase();
+  let (lookup_app, digest) = (app.clone(), base_sha256.clone());
+  let base = tauri::async_runtime::spawn_blocking(move || find_base(&lookup_app, &digest)).await.map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("modelo base {} no instalado", base_sha256))?;
+  if info.architecture.is_some() && base.architecture.is_some() && info.architecture != base.architecture {
+    return Err(format!(
+      "el adaptador es para {} y el modelo base es {}",
+      info.architecture.unwrap_or_default(), base.architecture.unwrap_or_default()
+    ));
+  }
+
+  let dir = adapters_dir(&app)?;
+  std::fs::create_dir_all(&dir).map_err(|e| format!("cannot create adapters dir: {}", e))?;
+  storage::preflight(&app, &dir, info.file_size)?;
+  let id = adapter_id(&src);
+  if !valid_id(&id) { return Err(format!("invalid adapter file name: {}", src.display())); }
+  let target = dir.join(format!("{}.gguf", id));
+  let part = dir.join(format!("{}.gguf.part", id));
+
+  crate::boot_log(&app, format!("[tauri] instalando adaptador {} sobre {}", src.display(), base.path)).await;
+  let (copy_src, copy_dst) = (src.clone(), part.clone());
+  let digest = tauri::async_runtime::spawn_blocking(move || sideload::copy_with_hash(&copy_src, &copy_dst, |_, _| {}))
+    .await.map_err(|e| e.to_string())?;
+  let digest = match digest {
+    Ok(d) => d,
+    Err(e) => { let _ = std::fs::remove_file(&part); return Err(e); }
+  };
+  if let Some(expected) = sha256.map(|s| s.trim().to_lowercase()) {
+    if expected != digest {
+      let _ = std::fs::remove_file(&part);
+      return Err(format!("sha256 mismatch: got {}, expected {}", digest, expected));
+    }
+  }
+  if let Err(e) = downloads::install_part(&part, &target, &digest) {
+    let _ = std::fs::remove_file(&part);
+    return Err(e);
+  }
+
+  let adapter = AdapterInfo {
+    name: name.unwrap_or_else(|| id.clone()),
+    id: id.clone(),
+    path: target.to_string_lossy().into_owned(),
+    sha256: digest,
+    size: info.file_size,
+    base_sha256,
+    base_model: base.name.or_else(|| Path::new(&base.path).file_name().map(|n| n.to_string_lossy().into_owned())),
+    architecture: info.architecture,
+    installed_at: now_secs(),
+  };
+  let meta = serde_json::to_vec_pretty(&adapter).map_err(|e| e.to_string())?;
+  std::fs::write(dir.join(format!("{}.json", id)), meta).map_err(|e| e.to_string())?;
+  // Los tags de Ollama del modelo base se recrean con el ADAPTER en el próximo ensure
+  crate::boot_log(&app, format!("[tauri] adaptador '{}' instalado", id)).await;
+  Ok(adapter)
+}
+
+#[tauri::command]
+pub fn remove_adapter(id: String, app: tauri::AppHandle) -> Result<(), String> {
+  if !valid_id(&id) { return Err(format!("invalid adapter id '{}'", id)); }
+  let dir = adapters_dir(&app)?;
+  let model = dir.join(format!("{}.gguf", id));
+  if !model.exists() && !dir.join(format!("{}.json", id)).exists() {
+    return Err(format!("adapter '{}' not found", id));
+  }
+  let _ = std::fs::remove_file(&model);
+  let _ = std::fs::remove_file(downloads::digest_sidecar(&model));
+  std::fs::remove_file(dir.join(format!("{}.json", id))).map_err(|e| e.to_string())?;
+  Ok(())
+}
@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+use crate::net::{self, RetryPolicy};
+use crate::ollama::{OllamaClient, OllamaError};
+use crate::{profiles, storage};
+
+// Clave pública Ed25519 con la que se firma el catálogo de modelos. Puede
+// sobrescribirse en tiempo de compilación con GANADO_CATALOG_PUBKEY (hex).
+const CATALOG_PUBLIC_KEY_HEX: &str = match option_env!("GANADO_CATALOG_PUBKEY") {
+  Some(key) => key,
+  None => "d2b0abb7a611168d832bf576a87c3f34b927ef121380d14d8b3d25c6ff71487b",
+};
+
+const DEFAULT_CATALOG_URL: &str = "https://app.ganado.co/api/models/catalog.json";
+// Modelo que instala AUTO_MODEL_SETUP cuando no hay ninguno
+pub const DEFAULT_MODEL_ID: &str = "deepseek-r1-qwen-1_5b";
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CatalogModel {
+  pub id: String,
+  pub name: String,
+  pub file_name: String,
+  pub quantization: String,
+  pub size_bytes: u64,
+  pub sha256: String,
+  pub min_ram_mb: u64,
+  pub ollama_tag: String,
+  pub url: String,
+  #[serde(default)]
+  pub mirrors: Vec<String>,
+  #[serde(default)]
+  pub description: Option<String>,
+}
+
+impl CatalogModel {
+  // Entradas aún sin `node scripts/sign-model-catalog.mjs --pin` no se pueden verificar
+  pub fn pinned(&self) -> bool {
+    self.sha256.len() == 64 && self.sha256.chars().all(|c| c.is_ascii_hexdigit())
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Catalog {
+  pub version: u32,
+  pub models: Vec<CatalogModel>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CatalogEntry {
+  #[serde(flatten)]
+  pub model: CatalogModel,
+  pub installed: bool,
+}
+
+// Verifica la firma separada (hex) sobre los bytes exactos del catálogo
+fn verify_catalog(bytes: &[u8], signature_hex: &str) -> Result<Catalog, String> {
+  verify_catalog_with(CATALOG_PUBLIC_KEY_HEX, bytes, signature_hex)
+}
+
+fn verify_catalog_with(public_key_hex: &str, bytes: &[u8], signature_hex: &str) -> Result<Catalog, String> {
+  let key_bytes: [u8; 32] = hex::decode(public_key_hex)
+    .map_err(|e| format!("invalid catalog public key: {}", e))?
+    .try_into()
+    .map_err(|_| "invalid catalog public key length".to_string())?;
+  let key = VerifyingKey::from_bytes(&key_bytes).map_err(|e| format!("invalid catalog public key: {}", e))?;
+  let sig_bytes: [u8; 64] = hex::decode(signature_hex.trim())
+    .map_err(|e| format!("invalid catalog signature: {}", e))?
+    .try_into()
+    .map_err(|_| "invalid catalog signature length".to_string())?;
+  key.verify(bytes, &Signature::from_bytes(&sig_bytes)).map_err(|_| "catalog signature verification failed".to_string())?;
+  serde_json::from_slice(bytes).map_err(|e| format!("invalid catalog: {}", e))
+}
+
+fn read_signed(path: &Path) -> Result<Catalog, String> {
+  let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+  let sig = std::fs::read_to_string(sig_path(path)).map_err(|e| e.to_string())?;
+  verify_catalog(&bytes, &sig)
+}
+
+fn sig_path(path: &Path) -> PathBuf {
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(".sig");
+  path.with_file_name(name)
+}
+
+async fn fetch_remote(client: &reqwest::Client, url: &str) -> Result<(Vec<u8>, String), String> {
+  // Pocos reintentos: sin red se cae rápido a la copia local
+  let policy = RetryPolicy { attempts_per_mirror: 2, ..RetryPolicy::default() };
+  let (bytes, _) = net::fetch_bytes(client, &[url.to_string()], &policy, |_| {}).await.map_err(|e| net::format_errors(&e))?;
+  let (sig, _) = net::fetch_bytes(client, &[format!("{}.sig", url)], &policy, |_| {}).await.map_err(|e| net::format_errors(&e))?;
+  Ok((bytes, String::from_utf8_lossy(&sig).into_owned()))
+}
+
+// Carga el catálogo: remoto (y se cachea), luego caché en app_data, luego el
+// empaquetado en Resources. Toda fuente se verifica con la clave embebida.
+pub async fn load_catalog(app: &tauri::AppHandle) -> Result<Catalog, String> {
+  let data_dir = crate::app_data_dir(app).ok_or("app_data_dir not found")?;
+  let cache_dir = data_dir.join("catalog");
+  let cache = cache_dir.join("catalog.json");
+  let url = std::env::var("MODEL_CATALOG_URL").unwrap_or_else(|_| DEFAULT_CATALOG_URL.to_string());
+
+  let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build().map_err(|e| e.to_string())?;
+  let mut last_err = match fetch_remote(&client, &url).await {
+    Ok((bytes, sig)) => match verify_catalog(&bytes, &sig) {
+      Ok(catalog) => {
+        let _ = std::fs::create_dir_all(&cache_dir);
+        let _ = std::fs::write(&cache, &bytes);
+        let _ = std::fs::write(sig_path(&cache), sig.as_bytes());
+        return Ok(catalog);
+      }
+      Err(e) => e,
+    },
+    Err(e) => e,
+  };
+  crate::boot_log(app, format!("[tauri] catálogo remoto no disponible ({}), usando copia local", last_err)).await;
+
+  let mut local = vec![cache];
+  if let Some(res) = app.path_resolver().resolve_resource("catalog/catalog.json") {
+    local.push(res);
+  }
+  for path in local {
+    if !path.exists() { continue; }
+    match read_signed(&path) {
+      Ok(catalog) => return Ok(catalog),
+      Err(e) => last_err = e,
+    }
+  }
+  Err(format!("no verified model catalog available: {}", last_err))
+}
+
+async fn find_entry(app: &tauri::AppHandle, id: &str) -> Result<CatalogModel, String> {
+  let catalog = load_catalog(app).await?;
+  catalog.models.into_iter().find(|m| m.id == id).ok_or_else(|| format!("model '{}' not in catalog", id))
+}
+
+#[tauri::command]
+pub async fn list_catalog_models(app: tauri::AppHandle) -> Result<Vec<CatalogEntry>, String> {
+  let catalog = load_catalog(&app).await?;
+  let models_dir = crate::app_data_dir(&app).ok_or("app_data_dir not found")?.join("models");
+  Ok(catalog.models.into_iter().map(|model| {
+    let installed = models_dir.join(&model.file_name).exists();
+    CatalogEntry { model, installed }
+  }).collect())
+}
+
+#[tauri::command]
+pub async fn install_model(id: String, app: tauri::AppHandle, window: tauri::Window) -> Result<String, String> {
+  let entry = find_entry(&app, &id).await?;
+  if !entry.pinned() {
+    return Err(format!("model '{}' has no pinned sha256 in the catalog; run scripts/sign-model-catalog.mjs --pin", entry.id));
+  }
+  crate::boot_log(&app, format!("[tauri] instalando modelo '{}' ({}, {} bytes)", entry.id, entry.quantization, entry.size_bytes)).await;
+  let urls = net::mirror_urls(&entry.url, &entry.mirrors, &entry.file_name);
+  let path = crate::downloads::download_model_as(urls, Some(entry.sha256.clone()), Some(entry.id.clone()), entry.file_name.clone(), app.clone(), window).await?;
+  crate::ensure_ollama_model_available(app.clone(), entry.ollama_tag.clone(), Some(path.clone())).await?;
+  Ok(path)
+}
+
+#[tauri::command]
+pub async fn uninstall_model(id: String, app: tauri::AppHandle) -> Result<(), String> {
+  let entry = find_entry(&app, &id).await?;
+  // Quitar el tag de Ollama primero para que no quede apuntando a un blob huérfano
+  if let Ok(ollama) = OllamaClient::from_state() {
+    match ollama.delete(&entry.ollama_tag).await {
+      Ok(()) | Err(OllamaError::NotFound { .. }) => {}
+      Err(e) => crate::boot_log(&app, format!("[tauri] no se pudo quitar '{}' de ollama: {}", entry.ollama_tag, e)).await,
+    }
+  }
+
+  let data_dir = crate::app_data_dir(&app).ok_or("app_data_dir not found")?;
+  // Otros tags creados desde el mismo GGUF también se quedarían sin modelo
+  storage::evict_from_ollama(&data_dir.join("ollama-store"), &entry.sha256).await;
+  profiles::forget_applied(&app, |tag, applied| tag == entry.ollama_tag || applied.gguf_sha256 == entry.sha256)?;
+
+  let path = data_dir.join("models").join(&entry.file_name);
+  if path.exists() {
+    std::fs::remove_file(&path).map_err(|e| format!("cannot remove {}: {}", path.display(), e))?;
+  }
+  let _ = std::fs::remove_file(crate::downloads::digest_sidecar(&path));
+  crate::boot_log(&app, format!("[tauri] modelo '{}' desinstalado", entry.id)).await;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ed25519_dalek::{Signer, SigningKey};
+
+  const CATALOG: &[u8] = br#"{"version":1,"models":[]}"#;
+
+  fn key() -> SigningKey {
+    SigningKey::from_bytes(&[7u8; 32])
+  }
+
+  fn sign(bytes: &[u8]) -> String {
+    hex::encode(key().sign(bytes).to_bytes())
+  }
+
+  fn public_hex() -> String {
+    hex::encode(key().verifying_key().to_bytes())
+  }
+
+  #[test]
+  fn accepts_signed_catalog() {
+    let catalog = verify_catalog_with(&public_hex(), CATALOG, &sign(CATALOG)).unwrap();
+    assert_eq!(catalog.version, 1);
+  }
+
+  #[test]
+  fn rejects_tampered_catalog() {
+    let sig = sign(CATALOG);
+    let tampered = br#"{"version":2,"models":[]}"#;
+    assert_eq!(verify_catalog_with(&public_hex(), tampered, &sig).unwrap_err(), "catalog signature verification failed");
+  }
+
+  #[test]
+  fn rejects_malformed_signature() {
+    assert!(verify_catalog_with(&public_hex(), CATALOG, "zz").unwrap_err().starts_with("invalid catalog signature"));
+    assert!(verify_catalog_with(&public_hex(), CATALOG, "abcd").unwrap_err().contains("length"));
+  }
+
+  #[test]
+  fn bundled_catalog_matches_embedded_key() {
+    let bytes = include_bytes!("../catalog/catalog.json");
+    let sig = include_str!("../catalog/catalog.json.sig");
+    let catalog = verify_catalog(bytes, sig).unwrap();
+    assert!(catalog.models.iter().any(|m| m.id == DEFAULT_MODEL_ID));
+  }
+
+  #[test]
+  fn unpinned_entry_is_not_installable() {
+    let mut model: CatalogModel = serde_json::from_str(r#"{"id":"m","name":"M","file_name":"m.gguf","quantization":"Q4_K_M","size_bytes":1,"sha256":"","min_ram_mb":1,"ollama_tag":"m:latest","url":"https://example.com/m.gguf"}"#).unwrap();
+    assert!(!model.pinned());
+    model.sha256 = "a".repeat(64);
+    assert!(model.pinned());
+    model.sha256 = "g".repeat(64);
+    assert!(!model.pinned());
+  }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use tauri::Manager;
+use tokio::sync::Notify;
+
+use crate::ollama::OllamaClient;
+use crate::ports;
+use crate::scheduler::{self, Priority, QueueError};
+use crate::services::ServiceKind;
+use crate::{llama_server, models};
+
+// Chat en streaming directo contra Ollama o llama-server, sin pasar por el proxy
+// del sidecar Node. Cada fragmento sale como evento `ai-token` con el requestId
+// del llamador; ai_cancel corta la conexión y el servidor deja de generar.
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ChatMessage {
+  pub role: String,
+  pub content: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatBackend {
+  Ollama,
+  Llama,
+}
+
+impl ChatBackend {
+  pub fn name(self) -> &'static str {
+    match self {
+      ChatBackend::Ollama => "ollama",
+      ChatBackend::Llama => "llama-server",
+    }
+  }
+
+  pub fn service(self) -> ServiceKind {
+    match self {
+      ChatBackend::Ollama => ServiceKind::Ollama,
+      ChatBackend::Llama => ServiceKind::Llama,
+    }
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatResult {
+  pub request_id: String,
+  pub backend: ChatBackend,
+  pub model: String,
+  pub content: String,
+  pub cancelled: bool,
+  pub eval_count: Option<u64>,
+}
+
+// Generaciones en curso: requestId -> señal de cancelación
+static ACTIVE: Lazy<std::sync::Mutex<HashMap<String, Arc<Notify>>>> = Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+// Quita la generación del registro al terminar por cualquier camino
+struct ActiveGuard(String);
+
+impl Drop for ActiveGuard {
+  fn drop(&mut self) {
+    ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.0);
+  }
+}
+
+fn register(request_id: &str) -> Result<(Arc<Notify>, ActiveGuard), String> {
+  let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+  if active.contains_key(request_id) { return Err(format!("request '{}' already running", request_id)); }
+  let cancel = Arc::new(Notify::new());
+  active.insert(request_id.to_string(), cancel.clone());
+  Ok((cancel, ActiveGuard(request_id.to_string())))
+}
+
+// Motor pedido o, si no se indica, el primero que esté sirviendo
+pub fn pick_backend(requested: Option<ChatBackend>) -> Result<ChatBackend, String> {
+  let running = |backend: ChatBackend| ports::get(backend.service()).is_some();
+  match requested {
+    Some(backend) if running(backend) => Ok(backend),
+    Some(backend) => Err(format!("{} no está corriendo", backend.name())),
+    None => [ChatBackend::Ollama, ChatBackend::Llama].into_iter().find(|b| running(*b))
+      .ok_or_else(|| "ningún motor de IA local está corriendo".to_string()),
+  }
+}
+
+// Opciones de Ollama traducidas a los campos de /v1/chat/completions
+fn openai_options(options: &serde_json::Map<String, serde_json::Value>) -> serde_json::Map<String, serde_json::Value> {
+  let mut out = serde_json::Map::new();
+  for (key, value) in options {
+    let key = match key.as_str() {
+      "num_predict" => "max_tokens",
+      "temperature" | "top_p" | "top_k" | "stop" | "seed" | "repeat_penalty" | "min_p" => key.as_str(),
+      _ => continue,
+    };
+    out.insert(key.to_string(), value.clone());
+  }
+  out
+}
+
+// Un fragmento de la respuesta de cada motor
+#[derive(Default)]
+struct Piece {
+  token: String,
+  thinking: String,
+  done: bool,
+  eval_count: Option<u64>,
+  error: Option<String>,
+}
+
+fn parse_ollama_line(line: &str) -> Option<Piece> {
+  let v: serde_json::Value = serde_json::from_str(line).ok()?;
+  Some(Piece {
+    token: v.pointer("/message/content").and_then(|c| c.as_str()).unwrap_or_default().to_string(),
+    thinking: v.pointer("/message/thinking").and_then(|c| c.as_str()).unwrap_or_default().to_string(),
+    done: v.get("done").and_then(|d| d.as_bool()).unwrap_or(false),
+    eval_count: v.get("eval_count").and_then(|c| c.as_u64()),
+    error: v.get("error").and_then(|e| e.as_str()).map(str::to_string),
+  })
+}
+
+// llama-server responde SSE: "data: {...}" y "data: [DONE]"
+fn parse_llama_line(line: &str) -> Option<Piece> {
+  let data = line.strip_prefix("data:")?.trim();
+  if data == "[DONE]" { return Some(Piece { done: true, ..Default::default() }); }
+  let v: serde_json::Value = serde_json::from_str(data).ok()?;
+  Some(Piece {
+    token: v.pointer("/choices/0/delta/content").and_then(|c| c.as_str()).unwrap_or_default().to_string(),
+    thinking: v.pointer("/choices/0/delta/reasoning_content").and_then(|c| c.as_str()).unwrap_or_default().to_string(),
+    done: false,
+    eval_count: v.pointer("/usage/completion_tokens").and_then(|c| c.as_u64()),
+    error: v.pointer("/error/message").and_then(|e| e.as_str()).map(str::to_string),
+  })
+}
+
+fn emit_token(app: &tauri::AppHandle, request_id: &str, piece: &Piece) {
+  let _ = app.emit_all("ai-token", serde_json::json!({
+    "requestId": request_id,
+    "token": piece.token,
+    "thinking": if piece.thinking.is_empty() { None } else { Some(&piece.thinking) },
+    "done": false,
+  }));
+}
+
+fn emit_done(app: &tauri::AppHandle, request_id: &str, cancelled: bool, error: Option<&str>) {
+  let _ = app.emit_all("ai-token", serde_json::json!({
+    "requestId": request_id,
+    "token": "",
+    "done": true,
+    "cancelled": cancelled,
+    "error": error,
+  }));
+}
+
+async fn open_stream(backend: ChatBackend, model: &str, messages: &[ChatMessage], options: &serde_json::Map<String, serde_json::Value>) -> Result<reqwest::Response, String> {
+  match backend {
+    ChatBackend::Ollama => {
+      let body = serde_json::json!({
+        "model": model,
+        "messages": messages,
+        "stream": true,
+        "keep_alive": "5m",
+        "options": options,
+      });
+      Ok(OllamaClient::from_state()?.chat_stream(&body).await?)
+    }
+    ChatBackend::Llama => {
+      let endpoint = ports::get(ServiceKind::Llama).ok_or("llama-server no está corriendo")?;
+      let mut body = openai_options(options);
+      body.insert("messages".into(), serde_json::json!(messages));
+      body.insert("stream".into(), serde_json::json!(true));
+      let client = reqwest::Client::builder().connect_timeout(Duration::from_secs(5)).build().map_err(|e| e.to_string())?;
+      let res = client.post(format!("{}/v1/chat/completions", endpoint.url)).json(&body).send().await.map_err(|e| e.to_string())?;
+      if !res.status().is_success() {
+        let status = res.status();
+        return Err(format!("llama-server respondió {}: {}", status, res.text().await.unwrap_or_default()));
+      }
+      Ok(res)
+    }
+  }
+}
+
+// Ejecuta la generación emitiendo `ai-token`; devuelve el texto completo
+pub async fn stream_chat(app: &tauri::AppHandle, request_id: &str, backend: ChatBackend, model: &str, messages: &[ChatMessage], options: &serde_json::Map<String, serde_json::Value>, priority: Priority) -> Result<ChatResult, String> {
+  let (cancel, _guard) = register(request_id)?;
+  let mut content = String::new();
+  let mut eval_count = None;
+  let mut cancelled = false;
+  let cancelled_result = |content: String, eval_count: Option<u64>| ChatResult {
+    request_id: request_id.to_string(), backend, model: model.to_string(), content, cancelled: true, eval_count,
+  };
+  // Turno en la cola del motor; el permiso se suelta al terminar la función
+  let permit = match scheduler::acquire(app, backend, request_id, priority, Some(&cancel)).await {
+    Ok(permit) => permit,
+    Err(QueueError::Cancelled) => {
+      emit_done(app, request_id, true, None);
+      return Ok(cancelled_result(content, eval_count));
+    }
+    Err(e) => { let e = e.to_string(); emit_done(app, request_id, false, Some(&e)); return Err(e); }
+  };
+  let timeout = permit.generation_timeout;
+  let deadline = tokio::time::sleep(timeout);
+  tokio::pin!(deadline);
+  let timed_out = || format!("la generación superó {} s", timeout.as_secs());
+  // Ollama no responde hasta cargar el modelo: la cancelación también vale aquí
+  let opened = tokio::select! {
+    _ = cancel.notified() => None,
+    _ = &mut deadline => Some(Err(timed_out())),
+    res = open_stream(backend, model, messages, options) => Some(res),
+  };
+  let res = match opened {
+    Some(Ok(res)) => res,
+    Some(Err(e)) => { emit_done(app, request_id, false, Some(&e)); return Err(e); }
+    None => {
+      emit_done(app, request_id, true, None);
+      return Ok(cancelled_result(content, eval_count));
+    }
+  };
+  let mut stream = res.bytes_stream();
+  let mut buf: Vec<u8> = Vec::new();
+  'read: loop {
+    let chunk = tokio::select! {
+      _ = cancel.notified() => { cancelled = true; break 'read; }
+      _ = &mut deadline => { let e = timed_out(); emit_done(app, request_id, false, Some(&e)); return Err(e); }
+      chunk = stream.next() => chunk,
+    };
+    let Some(chunk) = chunk else { break };
+    let chunk = match chunk {
+      Ok(c) => c,
+      Err(e) => { let e = e.to_string(); emit_done(app, request_id, false, Some(&e)); return Err(e); }
+    };
+    buf.extend_from_slice(&chunk);
+    while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+      let line: Vec<u8> = buf.drain(..=pos).collect();
+      let line = String::from_utf8_lossy(&line);
+      let line = line.trim();
+      if line.is_empty() { continue; }
+      let parsed = match backend {
+        ChatBackend::Ollama => parse_ollama_line(line),
+        ChatBackend::Llama => parse_llama_line(line),
+      };
+      let Some(piece) = parsed else { continue };
+      if let Some(e) = piece.error {
+        emit_done(app, request_id, false, Some(&e));
+        return Err(e);
+      }
+      if !piece.token.is_empty() || !piece.thinking.is_empty() {
+        content.push_str(&piece.token);
+        emit_token(app, request_id, &piece);
+      }
+      if piece.eval_count.is_some() { eval_count = piece.eval_count; }
+      if piece.done { break 'read; }
+    }
+  }
+  // Soltar el stream cierra la conexión: Ollama y llama-server abortan la generación
+  drop(stream);
+  emit_done(app, request_id, cancelled, None);
+  Ok(ChatResult { request_id: request_id.to_string(), backend, model: model.to_string(), content, cancelled, eval_count })
+}
+
+#[tauri::command]
+pub async fn ai_chat_stream(request_id: String, messages: Vec<ChatMessage>, model: Option<String>, backend: Option<ChatBackend>, options: Option<serde_json::Map<String, serde_json::Value>>, priority: Option<Priority>, app: tauri::AppHandle) -> Result<ChatResult, String> {
+  let backend = pick_backend(backend)?;
+  let model = match backend {
+    ChatBackend::Ollama => model.unwrap_or_else(|| models::default_model(&app)),
+    // llama-server sirve un único GGUF; el nombre es solo informativo
+    ChatBackend::Llama => llama_server::current_model()
+      .and_then(|p| std::path::Path::new(&p).file_name().map(|n| n.to_string_lossy().into_owned()))
+      .unwrap_or_default(),
+  };
+  stream_chat(&app, &request_id, backend, &model, &messages, &options.unwrap_or_default(), priority.unwrap_or_default()).await
+}
+
+// Cancela una generación en curso; false si ya terminó o no existe
+#[tauri::command]
+pub fn ai_cancel(request_id: String) -> bool {
+  match ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).get(&request_id) {
+    Some(cancel) => { cancel.notify_one(); true }
+    None => false,
+  }
+}
@@ -0,0 +1,129 @@
+// Detección de instrucciones de la CPU para elegir la build de inferencia:
+// en PCs viejas sin AVX2 una build genérica revienta (SIGILL) o va muy lenta.
+
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct CpuFeatures {
+  pub sse4_2: bool,
+  pub avx: bool,
+  pub avx2: bool,
+  pub fma: bool,
+  pub f16c: bool,
+  pub avx512f: bool,
+  pub neon: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CpuInfo {
+  pub arch: String,
+  pub brand: String,
+  pub physical_cores: usize,
+  pub logical_cores: usize,
+  pub features: CpuFeatures,
+  // Mejor nivel soportado: avx512, avx2, avx, sse4, neon o generic
+  pub level: String,
+}
+
+#[allow(unused_mut)]
+pub fn features() -> CpuFeatures {
+  let mut f = CpuFeatures::default();
+  #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+  {
+    f.sse4_2 = is_x86_feature_detected!("sse4.2");
+    f.avx = is_x86_feature_detected!("avx");
+    f.avx2 = is_x86_feature_detected!("avx2");
+    f.fma = is_x86_feature_detected!("fma");
+    f.f16c = is_x86_feature_detected!("f16c");
+    f.avx512f = is_x86_feature_detected!("avx512f");
+  }
+  #[cfg(target_arch = "aarch64")]
+  {
+    f.neon = std::arch::is_aarch64_feature_detected!("neon");
+  }
+  f
+}
+
+// Orden de preferencia de los niveles; mayor es mejor dentro de cada arquitectura
+pub fn rank(level: &str) -> u8 {
+  match level {
+    "avx512" => 4,
+    "avx2" | "neon" => 3,
+    "avx" => 2,
+    "sse4" => 1,
+    _ => 0,
+  }
+}
+
+// Si esta CPU puede ejecutar una build compilada para `level`. Las builds AVX2
+// de llama.cpp/ggml también usan FMA y F16C.
+pub fn supports(f: &CpuFeatures, level: &str) -> bool {
+  match level {
+    "avx512" => f.avx512f && f.avx2 && f.fma && f.f16c,
+    "avx2" => f.avx2 && f.fma && f.f16c,
+    "avx" => f.avx,
+    "sse4" => f.sse4_2,
+    "neon" => f.neon,
+    _ => true,
+  }
+}
+
+pub fn best_level(f: &CpuFeatures) -> &'static str {
+  ["avx512", "avx2", "neon", "avx", "sse4"].into_iter().find(|l| supports(f, l)).unwrap_or("generic")
+}
+
+// Niveles compatibles del mejor al peor, terminando en "generic"
+pub fn compatible_levels(f: &CpuFeatures) -> Vec<&'static str> {
+  let mut levels: Vec<&'static str> = ["avx512", "avx2", "neon", "avx", "sse4"].into_iter().filter(|l| supports(f, l)).collect();
+  levels.push("generic");
+  levels
+}
+
+pub fn info() -> CpuInfo {
+  let mut sys = sysinfo::System::new();
+  sys.refresh_cpu();
+  let f = features();
+  CpuInfo {
+    arch: std::env::consts::ARCH.to_string(),
+    brand: sys.cpus().first().map(|c| c.brand().trim().to_string()).unwrap_or_default(),
+    physical_cores: sys.physical_core_count().unwrap_or_else(|| sys.cpus().len().max(1)),
+    logical_cores: sys.cpus().len().max(1),
+    level: best_level(&f).to_string(),
+    features: f,
+  }
+}
+
+// Runner de Ollama a forzar (OLLAMA_LLM_LIBRARY) cuando la autodetección podría
+// elegir uno que esta CPU no soporta; None deja decidir a Ollama
+pub fn ollama_llm_library(f: &CpuFeatures) -> Option<&'static str> {
+  if !cfg!(any(target_arch = "x86", target_arch = "x86_64")) { return None; }
+  if supports(f, "avx2") { None } else if f.avx { Some("cpu_avx") } else { Some("cpu") }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SystemCapabilities {
+  pub os: String,
+  pub cpu: CpuInfo,
+  pub total_ram: u64,
+  pub available_ram: u64,
+  pub llama_variant: Option<String>,
+  pub llama_variant_error: Option<String>,
+  pub ollama_llm_library: Option<String>,
+}
+
+#[tauri::command]
+pub fn system_capabilities() -> SystemCapabilities {
+  let cpu = info();
+  let (total_ram, available_ram) = crate::models::memory_snapshot();
+  let (llama_variant, llama_variant_error) = match crate::llama_binary::pinned_asset() {
+    Ok((_, asset)) => (Some(asset.cpu.unwrap_or_else(|| "generic".into())), None),
+    Err(e) => (None, Some(e)),
+  };
+  SystemCapabilities {
+    os: std::env::consts::OS.to_string(),
+    ollama_llm_library: ollama_llm_library(&cpu.features).map(str::to_string),
+    cpu,
+    total_ram,
+    available_ram,
+    llama_variant,
+    llama_variant_error,
+  }
+}
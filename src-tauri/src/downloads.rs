@@ -0,0 +1,546 @@
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use tokio::sync::{watch, RwLock};
+
+use crate::lan;
+use crate::net::{self, MirrorError, RetryPolicy};
+use crate::storage;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadState {
+  Running,
+  Paused,
+  Cancelled,
+}
+
+// Descargas en curso indexadas por ID (por defecto, el nombre del archivo)
+static DOWNLOADS: Lazy<RwLock<HashMap<String, watch::Sender<DownloadState>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+// Último segmento de la ruta de la URL, validado para usarlo como nombre de archivo
+pub fn file_name_from_url(url: &str) -> Result<String, String> {
+  let path = url.split(['?', '#']).next().unwrap_or(url);
+  let name = path.rsplit('/').next().unwrap_or_default();
+  check_file_name(name)?;
+  Ok(name.to_string())
+}
+
+// Un nombre que al unirlo con join() pudiera salirse del directorio destino se
+// rechaza; los escapes % también, porque el nombre no se decodifica
+pub fn check_file_name(name: &str) -> Result<(), String> {
+  if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', ':', '%', '\0']) {
+    return Err(format!("invalid file name in URL: '{}'", name));
+  }
+  Ok(())
+}
+
+// .part propio de cada descarga: dos IDs con el mismo archivo no comparten bytes
+fn part_path(partial_dir: &Path, id: &str, filename: &str) -> PathBuf {
+  let key = hex::encode(Sha256::digest(id.as_bytes()));
+  partial_dir.join(format!("{}.{}.part", filename, &key[..12]))
+}
+
+// Validador HTTP (ETag fuerte o Last-Modified) del objeto que se está bajando
+// al .part; se envía como If-Range al reanudar
+fn validator_path(part: &Path) -> PathBuf {
+  let mut name = part.file_name().unwrap_or_default().to_os_string();
+  name.push(".validator");
+  part.with_file_name(name)
+}
+
+fn discard_part(part: &Path) {
+  let _ = std::fs::remove_file(part);
+  let _ = std::fs::remove_file(validator_path(part));
+}
+
+fn response_validator(res: &reqwest::Response) -> Option<String> {
+  let header = |name: reqwest::header::HeaderName| res.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+  // If-Range no admite ETags débiles
+  header(reqwest::header::ETAG).filter(|e| !e.starts_with("W/")).or_else(|| header(reqwest::header::LAST_MODIFIED))
+}
+
+// Primer byte de "Content-Range: bytes <inicio>-<fin>/<total>"
+fn content_range_start(value: &str) -> Option<u64> {
+  value.trim().strip_prefix("bytes")?.trim().split('-').next()?.trim().parse().ok()
+}
+
+// Qué hacer con la respuesta a una petición que pidió el archivo desde `offset`
+#[derive(Debug, PartialEq, Eq)]
+enum Resume {
+  // 416 con bytes previos: el .part ya contiene el archivo completo
+  Complete,
+  // 206 que empieza donde acaba el .part
+  Append,
+  // 206 desde otro byte: no se puede anexar, se descarta el .part
+  Mismatch,
+  // 200: el servidor ignoró el Range o el objeto cambió; se baja desde cero
+  Restart,
+  Failed,
+}
+
+fn resume_action(status: reqwest::StatusCode, offset: u64, content_range: Option<&str>) -> Resume {
+  if offset > 0 && status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE { return Resume::Complete; }
+  if !status.is_success() { return Resume::Failed; }
+  if status != reqwest::StatusCode::PARTIAL_CONTENT { return Resume::Restart; }
+  if content_range.and_then(content_range_start) == Some(offset) { Resume::Append } else { Resume::Mismatch }
+}
+
+// Etapas fijas que la UI recibe en `model-download-progress`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DownloadStage {
+  Downloading,
+  HashingPartial,
+  VerifyingFormat,
+  VerifyingHash,
+  Installing,
+  Completed,
+  Failed,
+  Paused,
+  Cancelled,
+}
+
+impl DownloadStage {
+  // Etapa que corresponde al estado de control: una pausa no es un atasco
+  fn for_state(state: DownloadState) -> Self {
+    match state {
+      DownloadState::Running => DownloadStage::Downloading,
+      DownloadState::Paused => DownloadStage::Paused,
+      DownloadState::Cancelled => DownloadStage::Cancelled,
+    }
+  }
+}
+
+fn emit_progress(window: &tauri::Window, id: &str, downloaded: u64, total: u64, state: DownloadState) {
+  let _ = window.emit("model-download-progress", serde_json::json!({
+    "id": id,
+    "stage": DownloadStage::for_state(state),
+    "downloaded": downloaded,
+    "total": total,
+    "state": state,
+  }));
+}
+
+fn emit_stage(window: &tauri::Window, id: &str, stage: DownloadStage, detail: serde_json::Value) {
+  let _ = window.emit("model-download-progress", serde_json::json!({
+    "id": id,
+    "stage": stage,
+    "detail": detail,
+  }));
+}
+
+fn emit_mirror_error(window: &tauri::Window, id: &str, err: &MirrorError) {
+  let _ = window.emit("model-download-mirror-error", serde_json::json!({
+    "id": id,
+    "url": err.url,
+    "attempt": err.attempt,
+    "error": err.error,
+  }));
+}
+
+#[tauri::command]
+pub async fn download_model(url: String, sha256_hex: Option<String>, download_id: Option<String>, mirrors: Option<Vec<String>>, app: tauri::AppHandle, window: tauri::Window) -> Result<String, String> {
+  let filename = file_name_from_url(&url)?;
+  let urls = net::mirror_urls(&url, &mirrors.unwrap_or_default(), &filename);
+  download_model_as(urls, sha256_hex, download_id, filename, app, window).await
+}
+
+// Igual que download_model pero con mirrors ya resueltos y nombre destino explícito
+pub async fn download_model_as(urls: Vec<String>, sha256_hex: Option<String>, download_id: Option<String>, filename: String, app: tauri::AppHandle, window: tauri::Window) -> Result<String, String> {
+  check_file_name(&filename)?;
+  let dir = crate::app_data_dir(&app).ok_or("app_data_dir not found")?;
+  let models_dir = dir.join("models");
+  if let Err(e) = create_dir_all(&models_dir) { return Err(format!("cannot create models dir: {}", e)); }
+  // Los .part viven fuera de models/ (mismo volumen) para que find_available_model
+  // nunca vea un archivo a medias y el rename final sea atómico
+  let partial_dir = dir.join("downloads");
+  create_dir_all(&partial_dir).map_err(|e| format!("cannot create downloads dir: {}", e))?;
+
+  let id = download_id.unwrap_or_else(|| filename.clone());
+  let part = part_path(&partial_dir, &id, &filename);
+  let target = models_dir.join(&filename);
+
+  let (tx, mut rx) = watch::channel(DownloadState::Running);
+  {
+    let mut guard = DOWNLOADS.write().await;
+    if guard.contains_key(&id) { return Err(format!("download '{}' already in progress", id)); }
+    guard.insert(id.clone(), tx);
+  }
+  // Un .part previo sin validador ni sha256 esperado no se puede atribuir a
+  // este archivo: se descarta en vez de anexarle bytes
+  if sha256_hex.is_none() && !validator_path(&part).exists() { discard_part(&part); }
+  let mut hasher = StreamHasher::default();
+  // Con el sha256 conocido se prefiere un equipo de la LAN que ya tenga el modelo;
+  // si ninguno lo completa se sigue por Internet desde lo ya escrito
+  let mut from_peer = false;
+  if let Some(expected) = sha256_hex.as_deref() {
+    for source in lan::find_model_sources(&app, expected).await {
+      emit_stage(&window, &id, DownloadStage::Downloading, serde_json::json!({ "source": "lan", "peer": source.peer }));
+      match fetch_from_peer(&app, &source, &part, &id, &window, &mut rx, &mut hasher).await {
+        Ok(()) => {
+          crate::boot_log(&app, format!("[tauri] {} descargado desde {} (LAN)", filename, source.peer)).await;
+          from_peer = true;
+          break;
+        }
+        Err(e) => {
+          emit_mirror_error(&window, &id, &MirrorError { url: source.base_url.clone(), attempt: 1, error: e });
+          if *rx.borrow() == DownloadState::Cancelled { break; }
+        }
+      }
+    }
+  }
+  let result = if from_peer { Ok(()) } else { fetch_resumable(&app, &urls, &part, &id, &window, &mut rx, &mut hasher).await };
+  DOWNLOADS.write().await.remove(&id);
+
+  if let Err(e) = result {
+    // Un error de red conserva el .part para reanudar; una cancelación lo descarta
+    if *rx.borrow() == DownloadState::Cancelled {
+      discard_part(&part);
+      emit_progress(&window, &id, 0, 0, DownloadState::Cancelled);
+    }
+    return Err(e);
+  }
+
+  let digest = hasher.finalize();
+  if let Err(e) = verify_part(&part, &digest, sha256_hex.as_deref(), &id, &window) {
+    discard_part(&part);
+    emit_stage(&window, &id, DownloadStage::Failed, serde_json::json!({ "error": e }));
+    return Err(e);
+  }
+
+  emit_stage(&window, &id, DownloadStage::Installing, serde_json::json!({ "target": target }));
+  if let Err(e) = install_part(&part, &target, &digest) {
+    discard_part(&part);
+    emit_stage(&window, &id, DownloadStage::Failed, serde_json::json!({ "error": e }));
+    return Err(e);
+  }
+  let _ = std::fs::remove_file(validator_path(&part));
+  storage::touch_model(&app, &target);
+  emit_stage(&window, &id, DownloadStage::Completed, serde_json::json!({ "path": target, "sha256": digest }));
+  Ok(target.to_string_lossy().into_owned())
+}
+
+// SHA-256 incremental que acompaña al .part: `len` indica cuántos bytes del
+// archivo ya están incorporados al hash.
+#[derive(Default)]
+struct StreamHasher {
+  inner: Sha256,
+  len: u64,
+}
+
+impl StreamHasher {
+  fn update(&mut self, bytes: &[u8]) {
+    self.inner.update(bytes);
+    self.len += bytes.len() as u64;
+  }
+
+  fn reset(&mut self) {
+    *self = StreamHasher::default();
+  }
+
+  // Re-hashea el prefijo ya descargado (p. ej. tras reiniciar la app con un .part)
+  fn rehash_prefix(&mut self, part: &Path, len: u64) -> Result<(), String> {
+    use std::io::Read;
+    self.reset();
+    let f = File::open(part).map_err(|e| e.to_string())?;
+    let mut reader = f.take(len);
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+      let n = reader.read(&mut buf).map_err(|e| e.to_string())?;
+      if n == 0 { break; }
+      self.update(&buf[..n]);
+    }
+    if self.len != len { return Err(format!("partial file shorter than expected ({} of {} bytes)", self.len, len)); }
+    Ok(())
+  }
+
+  fn finalize(self) -> String {
+    hex::encode(self.inner.finalize())
+  }
+}
+
+// Espera mientras la descarga esté en pausa; error si se cancela
+async fn wait_while_paused(control: &mut watch::Receiver<DownloadState>) -> Result<(), String> {
+  loop {
+    let state = *control.borrow_and_update();
+    match state {
+      DownloadState::Running => return Ok(()),
+      DownloadState::Cancelled => return Err("download cancelled".into()),
+      DownloadState::Paused => {}
+    }
+    if control.changed().await.is_err() { return Err("download control closed".into()); }
+  }
+}
+
+// Descarga sobre `part` bloque a bloque desde un equipo de la LAN. Se retoma en
+// el borde del bloque donde quedó el .part y cada bloque se verifica antes de
+// escribirlo; el sha256 completo se comprueba después como en cualquier descarga.
+async fn fetch_from_peer(app: &tauri::AppHandle, source: &lan::PeerSource, part: &Path, id: &str, window: &tauri::Window, control: &mut watch::Receiver<DownloadState>, hasher: &mut StreamHasher) -> Result<(), String> {
+  let manifest = &source.manifest;
+  let count = manifest.chunks.len() as u64;
+  let offset = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0).min(manifest.size);
+  let first = (offset / manifest.chunk_size).min(count);
+  let start = first * manifest.chunk_size;
+  if hasher.len != start {
+    if start > 0 {
+      emit_stage(window, id, DownloadStage::HashingPartial, serde_json::json!({ "bytes": start }));
+      hasher.rehash_prefix(part, start)?;
+    } else {
+      hasher.reset();
+    }
+  }
+  storage::preflight(app, part.parent().unwrap_or(part), manifest.size - start)?;
+  let mut file = OpenOptions::new().create(true).truncate(false).write(true).open(part).map_err(|e| e.to_string())?;
+  file.set_len(start).map_err(|e| e.to_string())?;
+  file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+  let mut writer = BufWriter::new(file);
+
+  let client = lan::peer_client()?;
+  let mut downloaded = start;
+  emit_progress(window, id, downloaded, manifest.size, DownloadState::Running);
+  for index in first..count {
+    if *control.borrow() != DownloadState::Running {
+      writer.flush().map_err(|e| e.to_string())?;
+      emit_progress(window, id, downloaded, manifest.size, *control.borrow());
+      wait_while_paused(control).await?;
+    }
+    let bytes = lan::fetch_chunk(&client, source, index).await?;
+    writer.write_all(&bytes).map_err(|e| e.to_string())?;
+    hasher.update(&bytes);
+    downloaded += bytes.len() as u64;
+    emit_progress(window, id, downloaded, manifest.size, DownloadState::Running);
+  }
+  writer.flush().map_err(|e| e.to_string())?;
+  Ok(())
+}
+
+// Descarga sobre `part` desde el primer mirror disponible, reanudando con Range
+// si ya hay bytes (también tras un corte a mitad del stream). Se pausa y reanuda
+// según `control` sin soltar el .part; el hash avanza con el stream. Con
+// validador guardado la reanudación va condicionada con If-Range.
+async fn fetch_resumable(app: &tauri::AppHandle, urls: &[String], part: &Path, id: &str, window: &tauri::Window, control: &mut watch::Receiver<DownloadState>, hasher: &mut StreamHasher) -> Result<(), String> {
+  let client = net::download_client()?;
+  let policy = RetryPolicy::default();
+  let max_stream_failures = policy.attempts_per_mirror * urls.len().max(1) as u32;
+  let mut stream_failures: u32 = 0;
+  loop {
+    wait_while_paused(control).await?;
+
+    let validator = std::fs::read_to_string(validator_path(part)).ok();
+    let offset = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+    let (res, mirror) = net::open_with_mirrors(&client, urls, &policy, offset, validator.as_deref(), |e| emit_mirror_error(window, id, e))
+      .await
+      .map_err(|errors| net::format_errors(&errors))?;
+    let status = res.status();
+    let content_range = res.headers().get(reqwest::header::CONTENT_RANGE).and_then(|v| v.to_str().ok());
+    let resumed = match resume_action(status, offset, content_range) {
+      Resume::Complete => {
+        if hasher.len != offset {
+          emit_stage(window, id, DownloadStage::HashingPartial, serde_json::json!({ "bytes": offset }));
+          hasher.rehash_prefix(part, offset)?;
+        }
+        return Ok(());
+      }
+      Resume::Failed => return Err(format!("download failed: HTTP {}", status)),
+      Resume::Mismatch => {
+        emit_mirror_error(window, id, &MirrorError { url: mirror, attempt: 1, error: format!("Content-Range does not start at byte {}; restarting", offset) });
+        discard_part(part);
+        hasher.reset();
+        continue;
+      }
+      Resume::Append => true,
+      Resume::Restart => false,
+    };
+    if resumed {
+      if hasher.len != offset {
+        emit_stage(window, id, DownloadStage::HashingPartial, serde_json::json!({ "bytes": offset }));
+        hasher.rehash_prefix(part, offset)?;
+      }
+    } else {
+      hasher.reset();
+      match response_validator(&res) {
+        Some(v) => { let _ = std::fs::write(validator_path(part), v); }
+        None => { let _ = std::fs::remove_file(validator_path(part)); }
+      }
+    }
+    let mut downloaded: u64 = if resumed { offset } else { 0 };
+    let total = res.content_length().map(|len| len + downloaded).unwrap_or(0);
+    // Rechazar antes de escribir si lo que falta no cabe en disco o en la cuota
+    storage::preflight(app, part.parent().unwrap_or(part), total.saturating_sub(offset))?;
+    let file = if resumed {
+      OpenOptions::new().append(true).open(part)
+    } else {
+      File::create(part)
+    }.map_err(|e| e.to_string())?;
+    let mut writer = BufWriter::new(file);
+    let mut stream = res.bytes_stream();
+    let mut interrupted = false;
+    let mut stream_error: Option<String> = None;
+    emit_progress(window, id, downloaded, total, DownloadState::Running);
+
+    loop {
+      tokio::select! {
+        chunk = stream.next() => match chunk {
+          Some(Ok(bytes)) => {
+            writer.write_all(&bytes).map_err(|e| e.to_string())?;
+            hasher.update(&bytes);
+            downloaded += bytes.len() as u64;
+            emit_progress(window, id, downloaded, total, DownloadState::Running);
+          }
+          Some(Err(e)) => {
+            stream_error = Some(e.to_string());
+            break;
+          }
+          None => break,
+        },
+        changed = control.changed() => {
+          if changed.is_err() { return Err("download control closed".into()); }
+          if *control.borrow() != DownloadState::Running { interrupted = true; break; }
+        }
+      }
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+
+    if interrupted {
+      let state = *control.borrow();
+      emit_progress(window, id, downloaded, total, state);
+      continue;
+    }
+    // Corte a mitad del stream: reconectar desde lo ya escrito con backoff.
+    // Si hubo avance el contador se reinicia (enlaces rurales inestables).
+    if let Some(error) = stream_error {
+      stream_failures = if downloaded > offset { 1 } else { stream_failures + 1 };
+      emit_mirror_error(window, id, &MirrorError { url: mirror, attempt: stream_failures, error: error.clone() });
+      if stream_failures >= max_stream_failures {
+        return Err(format!("download interrupted: {}", error));
+      }
+      tokio::time::sleep(policy.delay(stream_failures - 1)).await;
+      continue;
+    }
+    if total > 0 && downloaded < total {
+      return Err(format!("download incomplete ({} of {} bytes)", downloaded, total));
+    }
+    return Ok(());
+  }
+}
+
+fn verify_part(part: &Path, digest: &str, sha256_hex: Option<&str>, id: &str, window: &tauri::Window) -> Result<(), String> {
+  // El header GGUF debe ser válido y los tensores que declara deben estar completos
+  emit_stage(window, id, DownloadStage::VerifyingFormat, serde_json::Value::Null);
+  let info = crate::gguf::inspect(part)?;
+  if info.tensor_count == 0 { return Err("downloaded GGUF has no tensors".into()); }
+
+  emit_stage(window, id, DownloadStage::VerifyingHash, serde_json::json!({ "sha256": digest }));
+  if let Some(expected) = sha256_hex {
+    if digest != expected.to_lowercase() {
+      return Err(format!("sha256 mismatch: got {}, expected {}", digest, expected));
+    }
+  }
+  Ok(())
+}
+
+// Mueve el .part verificado a su destino final con un rename atómico y deja
+// junto al modelo su sha256 (`<archivo>.sha256`) para no tener que recalcularlo
+pub fn install_part(part: &Path, target: &Path, digest: &str) -> Result<(), String> {
+  OpenOptions::new().write(true).open(part).and_then(|f| f.sync_all()).map_err(|e| e.to_string())?;
+  std::fs::rename(part, target).map_err(|e| format!("cannot move download into models dir: {}", e))?;
+  write_digest_sidecar(target, digest);
+  Ok(())
+}
+
+pub fn write_digest_sidecar(model: &Path, digest: &str) {
+  let _ = std::fs::write(digest_sidecar(model), format!("{}  {}\n", digest, model.file_name().unwrap_or_default().to_string_lossy()));
+}
+
+pub fn digest_sidecar(model: &Path) -> std::path::PathBuf {
+  let mut name = model.file_name().unwrap_or_default().to_os_string();
+  name.push(".sha256");
+  model.with_file_name(name)
+}
+
+// Lee un sidecar en formato sha256sum ("<hex>  <archivo>") o solo el hex
+pub fn read_digest_sidecar(model: &Path) -> Option<String> {
+  let text = std::fs::read_to_string(digest_sidecar(model)).ok()?;
+  let hex = text.split_whitespace().next()?.to_lowercase();
+  if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) { Some(hex) } else { None }
+}
+
+// sha256 de un modelo instalado: el del sidecar o, si falta, se calcula y se guarda
+pub fn model_digest(model: &Path) -> Result<String, String> {
+  if let Some(digest) = read_digest_sidecar(model) { return Ok(digest); }
+  let mut file = File::open(model).map_err(|e| format!("cannot open {}: {}", model.display(), e))?;
+  let mut hasher = Sha256::new();
+  std::io::copy(&mut file, &mut hasher).map_err(|e| format!("read error on {}: {}", model.display(), e))?;
+  let digest = hex::encode(hasher.finalize());
+  write_digest_sidecar(model, &digest);
+  Ok(digest)
+}
+
+async fn set_state(id: &str, state: DownloadState) -> Result<(), String> {
+  let guard = DOWNLOADS.read().await;
+  let control = guard.get(id).ok_or_else(|| format!("download '{}' not found", id))?;
+  control.send(state).map_err(|_| format!("download '{}' is no longer running", id))
+}
+
+#[tauri::command]
+pub async fn pause_download(download_id: String) -> Result<(), String> {
+  set_state(&download_id, DownloadState::Paused).await
+}
+
+#[tauri::command]
+pub async fn resume_download(download_id: String) -> Result<(), String> {
+  set_state(&download_id, DownloadState::Running).await
+}
+
+#[tauri::command]
+pub async fn cancel_download(download_id: String) -> Result<(), String> {
+  set_state(&download_id, DownloadState::Cancelled).await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use reqwest::StatusCode;
+
+  #[test]
+  fn parses_content_range_start() {
+    assert_eq!(content_range_start("bytes 100-199/200"), Some(100));
+    assert_eq!(content_range_start(" bytes  0-9/*"), Some(0));
+    assert_eq!(content_range_start("bytes */200"), None);
+    assert_eq!(content_range_start("items 1-2/3"), None);
+  }
+
+  #[test]
+  fn appends_only_when_range_starts_at_offset() {
+    assert_eq!(resume_action(StatusCode::PARTIAL_CONTENT, 100, Some("bytes 100-199/200")), Resume::Append);
+    assert_eq!(resume_action(StatusCode::PARTIAL_CONTENT, 100, Some("bytes 0-199/200")), Resume::Mismatch);
+    assert_eq!(resume_action(StatusCode::PARTIAL_CONTENT, 100, None), Resume::Mismatch);
+  }
+
+  #[test]
+  fn full_response_restarts_the_download() {
+    // If-Range no coincidió o el servidor ignora Range
+    assert_eq!(resume_action(StatusCode::OK, 100, None), Resume::Restart);
+    assert_eq!(resume_action(StatusCode::OK, 0, None), Resume::Restart);
+  }
+
+  #[test]
+  fn range_not_satisfiable_means_complete_only_with_bytes() {
+    assert_eq!(resume_action(StatusCode::RANGE_NOT_SATISFIABLE, 200, None), Resume::Complete);
+    assert_eq!(resume_action(StatusCode::RANGE_NOT_SATISFIABLE, 0, None), Resume::Failed);
+    assert_eq!(resume_action(StatusCode::NOT_FOUND, 100, None), Resume::Failed);
+  }
+
+  #[test]
+  fn part_path_depends_on_download_id() {
+    let dir = Path::new("/tmp/downloads");
+    assert_ne!(part_path(dir, "a", "m.gguf"), part_path(dir, "b", "m.gguf"));
+    assert_eq!(part_path(dir, "a", "m.gguf"), part_path(dir, "a", "m.gguf"));
+  }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+
+use crate::chat::ChatBackend;
+use crate::ollama::OllamaClient;
+use crate::scheduler::{self, Priority};
+use crate::services::ServiceKind;
+use crate::{llama_server, models, ports};
+
+// Gateway OpenAI-compatible en 127.0.0.1: /v1/chat/completions, /v1/embeddings
+// y /v1/models. Reenvía a Ollama o a llama-server (ambos hablan /v1) según cuál
+// esté sano y, si uno falla antes de responder, reintenta con el otro. El
+// frontend y las integraciones locales solo necesitan esta URL.
+
+// Puerto preferido; GANADO_GATEWAY_PORT lo cambia y si está ocupado se usa otro
+const DEFAULT_PORT: u16 = 4318;
+// Tras un fallo el motor pasa al final de la cola durante este tiempo
+const FAILURE_COOLDOWN: Duration = Duration::from_secs(15);
+// Al cerrar, espera máxima a que terminen las respuestas en curso (streams SSE)
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
+struct GatewayState {
+  app: tauri::AppHandle,
+  http: reqwest::Client,
+}
+
+// Señal de cierre y tarea del servidor en marcha
+type Running = (tokio::sync::oneshot::Sender<()>, tauri::async_runtime::JoinHandle<()>);
+static SERVER: Lazy<std::sync::Mutex<Option<Running>>> = Lazy::new(|| std::sync::Mutex::new(None));
+
+static FAILURES: Lazy<std::sync::Mutex<HashMap<ChatBackend, Instant>>> = Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+fn mark_failed(backend: ChatBackend) {
+  FAILURES.lock().unwrap_or_else(|e| e.into_inner()).insert(backend, Instant::now());
+}
+
+fn recently_failed(backend: ChatBackend) -> bool {
+  FAILURES.lock().unwrap_or_else(|e| e.into_inner()).get(&backend).is_some_and(|t| t.elapsed() < FAILURE_COOLDOWN)
+}
+
+fn llama_model_name() -> Option<String> {
+  llama_server::current_model()
+    .and_then(|p| std::path::Path::new(&p).file_name().map(|n| n.to_string_lossy().into_owned()))
+}
+
+// Motores a probar, en orden: el dueño del modelo pedido primero y los que
+// fallaron hace poco al final
+fn route(model: Option<&str>) -> Vec<ChatBackend> {
+  let llama_first = model.is_some() && model.map(str::to_string) == llama_model_name();
+  let mut order = if llama_first { vec![ChatBackend::Llama, ChatBackend::Ollama] } else { vec![ChatBackend::Ollama, ChatBackend::Llama] };
+  order.retain(|b| ports::get(b.service()).is_some());
+  order.sort_by_key(|b| recently_failed(*b));
+  order
+}
+
+fn openai_error(status: StatusCode, message: &str) -> Response {
+  (status, Json(serde_json::json!({ "error": { "message": message, "type": "gateway_error" } }))).into_response()
+}
+
+// Reenvía `path` al primer motor que responda sin error de servidor
+async fn forward(state: &GatewayState, path: &str, headers: &HeaderMap, body: Bytes) -> Response {
+  let Ok(mut request) = serde_json::from_slice::<serde_json::Value>(&body) else {
+    return openai_error(StatusCode::BAD_REQUEST, "invalid JSON body");
+  };
+  let requested = request.get("model").and_then(|m| m.as_str()).filter(|m| !m.is_empty() && *m != "default").map(str::to_string);
+  // El modelo de chat por defecto no genera embeddings útiles: hay que nombrarlo
+  let embeddings = path == "/v1/embeddings";
+  if embeddings && requested.is_none() {
+    return openai_error(StatusCode::BAD_REQUEST, "model is required for /v1/embeddings");
+  }
+  let backends = route(requested.as_deref());
+  if backends.is_empty() { return openai_error(StatusCode::SERVICE_UNAVAILABLE, "no local inference backend is running"); }
+  // X-Ganado-Priority: background para trabajos sin nadie esperando
+  let priority = match headers.get("x-ganado-priority").and_then(|v| v.to_str().ok()) {
+    Some("background") => Priority::Background,
+    _ => Priority::Interactive,
+  };
+  let request_id = format!("gw-{:08x}", rand::random::<u32>());
+  let mut last_error = String::new();
+  for (i, backend) in backends.iter().enumerate() {
+    let Some(endpoint) = ports::get(backend.service()) else { continue };
+    let permit = match scheduler::acquire(&state.app, *backend, &request_id, priority, None).await {
+      Ok(permit) => permit,
+      Err(e) => {
+        last_error = e.to_string();
+        continue;
+      }
+    };
+    // Ollama necesita un tag; un nombre de GGUF de llama-server no le sirve
+    let model = match backend {
+      ChatBackend::Ollama if embeddings => requested.clone().unwrap_or_default(),
+      ChatBackend::Ollama => requested.clone().filter(|m| Some(m) != llama_model_name().as_ref()).unwrap_or_else(|| models::default_model(&state.app)),
+      ChatBackend::Llama => llama_model_name().unwrap_or_default(),
+    };
+    request["model"] = serde_json::json!(model);
+    let res = state.http.post(format!("{}{}", endpoint.url, path)).json(&request).send().await;
+    let res = match res {
+      Ok(res) => res,
+      Err(e) => {
+        mark_failed(*backend);
+        last_error = format!("{}: {}", backend.name(), e);
+        continue;
+      }
+    };
+    let status = res.status();
+    let retryable = status.is_server_error() || status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::NOT_IMPLEMENTED;
+    if retryable && i + 1 < backends.len() {
+      if status.is_server_error() { mark_failed(*backend); }
+      last_error = format!("{}: {}", backend.name(), status);
+      continue;
+    }
+    let mut response = Response::builder()
+      .status(status.as_u16())
+      .header("x-ganado-backend", backend.name());
+    let content_type = res.headers().get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(str::to_string);
+    if let Some(ct) = &content_type {
+      response = response.header(header::CONTENT_TYPE, ct);
+    }
+    let sse = content_type.is_some_and(|ct| ct.starts_with("text/event-stream"));
+    // El cuerpo se transmite tal cual (incluido SSE con stream: true); el turno
+    // se libera al terminar o al agotar generation_timeout
+    return response.body(Body::from_stream(with_deadline(res, permit, sse)))
+      .unwrap_or_else(|e| openai_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()));
+  }
+  openai_error(StatusCode::BAD_GATEWAY, &format!("all backends failed ({})", last_error))
+}
+
+// Corta el cuerpo al agotar generation_timeout sin que parezca una respuesta
+// completa: en SSE cierra con un evento de error y en JSON aborta la conexión
+fn with_deadline(res: reqwest::Response, permit: scheduler::Permit, sse: bool) -> impl futures_util::Stream<Item = Result<Bytes, std::io::Error>> + Send {
+  let deadline = Box::pin(tokio::time::sleep(permit.generation_timeout));
+  let upstream = res.bytes_stream().boxed();
+  futures_util::stream::unfold(Some((upstream, deadline, permit)), move |state| async move {
+    let (mut upstream, mut deadline, permit) = state?;
+    tokio::select! {
+      chunk = upstream.next() => {
+        let chunk = chunk?.map_err(std::io::Error::other);
+        Some((chunk, Some((upstream, deadline, permit))))
+      }
+      _ = &mut deadline => {
+        let message = format!("generation exceeded {} s", permit.generation_timeout.as_secs());
+        let item = if sse {
+          let event = serde_json::json!({ "error": { "message": message, "type": "timeout" } });
+          Ok(Bytes::from(format!("data: {}\n\n", event)))
+        } else {
+          Err(std::io::Error::new(std::io::ErrorKind::TimedOut, message))
+        };
+        // Sin estado siguiente el stream termina y el permiso se suelta
+        Some((item, None))
+      }
+    }
+  })
+}
+
+async fn chat_handler(State(state): State<Arc<GatewayState>>, headers: HeaderMap, body: Bytes) -> Response {
+  forward(&state, "/v1/chat/completions", &headers, body).await
+}
+
+async fn embeddings_handler(State(state): State<Arc<GatewayState>>, headers: HeaderMap, body: Bytes) -> Response {
+  forward(&state, "/v1/embeddings", &headers, body).await
+}
+
+async fn models_handler() -> Json<serde_json::Value> {
+  let mut data: Vec<serde_json::Value> = Vec::new();
+  if let Ok(client) = OllamaClient::from_state() {
+    for m in client.tags().await.unwrap_or_default() {
+      data.push(serde_json::json!({ "id": m.name, "object": "model", "created": 0, "owned_by": "ollama" }));
+    }
+  }
+  if let Some(name) = llama_model_name() {
+    data.push(serde_json::json!({ "id": name, "object": "model", "created": 0, "owned_by": "llama-server" }));
+  }
+  Json(serde_json::json!({ "object": "list", "data": data }))
+}
+
+fn is_loopback_origin(origin: &str) -> bool {
+  ["http://127.0.0.1", "http://localhost", "tauri://localhost", "https://tauri.localhost"].iter().any(|allowed| {
+    origin == *allowed || origin.strip_prefix(allowed).is_some_and(|rest| rest.starts_with(':'))
+  })
+}
+
+// Solo atiende peticiones dirigidas a 127.0.0.1/localhost (evita DNS rebinding)
+// y solo habilita CORS para orígenes locales (la UI y la app de escritorio)
+async fn loopback_only(req: Request, next: Next) -> Response {
+  let host = req.headers().get(header::HOST).and_then(|h| h.to_str().ok()).unwrap_or_default();
+  let host_name = host.rsplit_once(':').map(|(h, _)| h).unwrap_or(host);
+  if host_name != "127.0.0.1" && host_name != "localhost" {
+    return openai_error(StatusCode::FORBIDDEN, "gateway only accepts loopback requests");
+  }
+  let origin = req.headers().get(header::ORIGIN).and_then(|o| o.to_str().ok()).map(str::to_string);
+  if origin.as_deref().is_some_and(|o| !is_loopback_origin(o)) {
+    return openai_error(StatusCode::FORBIDDEN, "origin not allowed");
+  }
+  let mut response = if req.method() == Method::OPTIONS { StatusCode::NO_CONTENT.into_response() } else { next.run(req).await };
+  if let Some(origin) = origin.and_then(|o| HeaderValue::from_str(&o).ok()) {
+    let headers = response.headers_mut();
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("GET, POST, OPTIONS"));
+    headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, HeaderValue::from_static("content-type, authorization, x-ganado-priority"));
+  }
+  response
+}
+
+// Arranca el gateway en segundo plano y registra su puerto (endpoint "gateway")
+pub async fn start(app: &tauri::AppHandle) -> Result<u16, String> {
+  let preferred = std::env::var("GANADO_GATEWAY_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(DEFAULT_PORT);
+  let port = ports::pick(preferred)?;
+  let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await
+    .map_err(|e| format!("cannot listen on port {}: {}", port, e))?;
+  let http = reqwest::Client::builder().connect_timeout(Duration::from_secs(3)).build().map_err(|e| e.to_string())?;
+  let state = Arc::new(GatewayState { app: app.clone(), http });
+  let router = Router::new()
+    .route("/v1/chat/completions", post(chat_handler))
+    .route("/v1/embeddings", post(embeddings_handler))
+    .route("/v1/models", get(models_handler))
+    .layer(middleware::from_fn(loopback_only))
+    .with_state(state);
+  let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+  let task = tauri::async_runtime::spawn(async move {
+    let _ = axum::serve(listener, router).with_graceful_shutdown(async { let _ = stop_rx.await; }).await;
+  });
+  *SERVER.lock().unwrap_or_else(|e| e.into_inner()) = Some((stop_tx, task));
+  ports::record(app, ServiceKind::Gateway, port, true);
+  crate::boot_log(app, format!("[tauri] gateway OpenAI en http://127.0.0.1:{}/v1", port)).await;
+  Ok(port)
+}
+
+// Deja de aceptar conexiones y espera (hasta SHUTDOWN_GRACE) a que terminen las
+// respuestas en curso, para que liberen sus turnos del scheduler antes de que se
+// detengan los motores. Llamarla de nuevo no hace nada.
+pub async fn shutdown(app: &tauri::AppHandle) {
+  let Some((stop_tx, mut task)) = SERVER.lock().unwrap_or_else(|e| e.into_inner()).take() else { return };
+  let _ = stop_tx.send(());
+  if tokio::time::timeout(SHUTDOWN_GRACE, &mut task).await.is_err() {
+    task.abort();
+    crate::boot_log(app, "[tauri] gateway cerrado con respuestas en curso".to_string()).await;
+  }
+  ports::forget(app, ServiceKind::Gateway);
+}
@@ -0,0 +1,446 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+const DEFAULT_ALIGNMENT: u64 = 32;
+// Límites defensivos para no reservar memoria absurda con un archivo corrupto
+const MAX_STRING_LEN: u64 = 16 * 1024 * 1024;
+const MAX_COUNT: u64 = 1 << 24;
+// Arreglos de arreglos: ningún modelo real pasa de dos niveles
+const MAX_ARRAY_DEPTH: u32 = 8;
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct GgufInfo {
+  pub path: String,
+  pub file_size: u64,
+  pub version: u32,
+  pub architecture: Option<String>,
+  pub name: Option<String>,
+  pub parameter_count: u64,
+  pub quantization: Option<String>,
+  pub context_length: Option<u64>,
+  pub chat_template: Option<String>,
+  pub block_count: Option<u64>,
+  pub embedding_length: Option<u64>,
+  pub head_count: Option<u64>,
+  pub head_count_kv: Option<u64>,
+  pub tensor_count: u64,
+  // Bytes que ocupan los tensores según el header (fin esperado del archivo)
+  pub expected_size: u64,
+  pub bits_per_weight: f64,
+  // "lora" en adaptadores (adapter.type); None en modelos completos
+  pub adapter_type: Option<String>,
+}
+
+// Valor de metadata; solo se conservan los tipos que nos interesan
+enum Value {
+  Uint(u64),
+  Int(i64),
+  Str(String),
+  Other,
+}
+
+struct Reader<R> {
+  inner: R,
+  version: u32,
+  pos: u64,
+}
+
+impl<R: Read + Seek> Reader<R> {
+  fn bytes<const N: usize>(&mut self) -> Result<[u8; N], String> {
+    let mut buf = [0u8; N];
+    self.inner.read_exact(&mut buf).map_err(|_| "unexpected end of file (truncated GGUF header)".to_string())?;
+    self.pos += N as u64;
+    Ok(buf)
+  }
+
+  fn u32(&mut self) -> Result<u32, String> { Ok(u32::from_le_bytes(self.bytes()?)) }
+  fn u64(&mut self) -> Result<u64, String> { Ok(u64::from_le_bytes(self.bytes()?)) }
+
+  // GGUF v1 usaba u32 para longitudes y contadores
+  fn len(&mut self) -> Result<u64, String> {
+    if self.version == 1 { Ok(self.u32()? as u64) } else { self.u64() }
+  }
+
+  fn skip(&mut self, n: u64) -> Result<(), String> {
+    let n = i64::try_from(n).map_err(|_| "invalid GGUF length".to_string())?;
+    self.inner.seek_relative(n).map_err(|e| e.to_string())?;
+    self.pos += n as u64;
+    Ok(())
+  }
+
+  fn string(&mut self) -> Result<String, String> {
+    let len = self.len()?;
+    if len > MAX_STRING_LEN { return Err(format!("GGUF string too long ({} bytes)", len)); }
+    let mut buf = vec![0u8; len as usize];
+    self.inner.read_exact(&mut buf).map_err(|_| "unexpected end of file (truncated GGUF string)".to_string())?;
+    self.pos += len;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+  }
+
+  fn skip_string(&mut self) -> Result<(), String> {
+    let len = self.len()?;
+    if len > MAX_STRING_LEN { return Err(format!("GGUF string too long ({} bytes)", len)); }
+    self.skip(len)
+  }
+
+  fn value(&mut self, ty: u32) -> Result<Value, String> {
+    Ok(match ty {
+      0 => Value::Uint(self.bytes::<1>()?[0] as u64),
+      1 => Value::Int(self.bytes::<1>()?[0] as i8 as i64),
+      2 => Value::Uint(u16::from_le_bytes(self.bytes()?) as u64),
+      3 => Value::Int(i16::from_le_bytes(self.bytes()?) as i64),
+      4 => Value::Uint(self.u32()? as u64),
+      5 => Value::Int(i32::from_le_bytes(self.bytes()?) as i64),
+      6 => { self.skip(4)?; Value::Other }
+      7 => { self.skip(1)?; Value::Other }
+      8 => Value::Str(self.string()?),
+      9 => { self.skip_array(1)?; Value::Other }
+      10 => Value::Uint(self.u64()?),
+      11 => Value::Int(i64::from_le_bytes(self.bytes()?)),
+      12 => { self.skip(8)?; Value::Other }
+      other => return Err(format!("unknown GGUF metadata type {}", other)),
+    })
+  }
+
+  // Los arreglos (p. ej. el vocabulario) pueden ser enormes: se saltan sin guardarlos
+  fn skip_array(&mut self, depth: u32) -> Result<(), String> {
+    if depth > MAX_ARRAY_DEPTH { return Err(format!("GGUF arrays nested deeper than {} levels", MAX_ARRAY_DEPTH)); }
+    let ty = self.u32()?;
+    let count = self.len()?;
+    if count > MAX_COUNT { return Err(format!("GGUF array too long ({} items)", count)); }
+    match scalar_size(ty) {
+      Some(size) => self.skip(size * count),
+      None if ty == 8 => {
+        for _ in 0..count { self.skip_string()?; }
+        Ok(())
+      }
+      None if ty == 9 => {
+        for _ in 0..count { self.skip_array(depth + 1)?; }
+        Ok(())
+      }
+      None => Err(format!("unknown GGUF array type {}", ty)),
+    }
+  }
+}
+
+fn scalar_size(ty: u32) -> Option<u64> {
+  match ty {
+    0 | 1 | 7 => Some(1),
+    2 | 3 => Some(2),
+    4..=6 => Some(4),
+    10..=12 => Some(8),
+    _ => None,
+  }
+}
+
+// (elementos por bloque, bytes por bloque) de cada ggml_type
+fn ggml_type_size(ty: u32) -> Option<(u64, u64)> {
+  Some(match ty {
+    0 => (1, 4),       // F32
+    1 => (1, 2),       // F16
+    2 => (32, 18),     // Q4_0
+    3 => (32, 20),     // Q4_1
+    6 => (32, 22),     // Q5_0
+    7 => (32, 24),     // Q5_1
+    8 => (32, 34),     // Q8_0
+    9 => (32, 36),     // Q8_1
+    10 => (256, 84),   // Q2_K
+    11 => (256, 110),  // Q3_K
+    12 => (256, 144),  // Q4_K
+    13 => (256, 176),  // Q5_K
+    14 => (256, 210),  // Q6_K
+    15 => (256, 292),  // Q8_K
+    16 => (256, 66),   // IQ2_XXS
+    17 => (256, 74),   // IQ2_XS
+    18 => (256, 98),   // IQ3_XXS
+    19 => (256, 50),   // IQ1_S
+    20 => (32, 18),    // IQ4_NL
+    21 => (256, 110),  // IQ3_S
+    22 => (256, 82),   // IQ2_S
+    23 => (256, 136),  // IQ4_XS
+    24 => (1, 1),      // I8
+    25 => (1, 2),      // I16
+    26 => (1, 4),      // I32
+    27 => (1, 8),      // I64
+    28 => (1, 8),      // F64
+    29 => (256, 56),   // IQ1_M
+    30 => (1, 2),      // BF16
+    34 => (256, 54),   // TQ1_0
+    35 => (256, 66),   // TQ2_0
+    _ => return None,
+  })
+}
+
+fn ggml_type_name(ty: u32) -> &'static str {
+  match ty {
+    0 => "F32", 1 => "F16", 2 => "Q4_0", 3 => "Q4_1", 6 => "Q5_0", 7 => "Q5_1", 8 => "Q8_0", 9 => "Q8_1",
+    10 => "Q2_K", 11 => "Q3_K", 12 => "Q4_K", 13 => "Q5_K", 14 => "Q6_K", 15 => "Q8_K",
+    16 => "IQ2_XXS", 17 => "IQ2_XS", 18 => "IQ3_XXS", 19 => "IQ1_S", 20 => "IQ4_NL", 21 => "IQ3_S",
+    22 => "IQ2_S", 23 => "IQ4_XS", 29 => "IQ1_M", 30 => "BF16", 34 => "TQ1_0", 35 => "TQ2_0",
+    _ => "unknown",
+  }
+}
+
+// Nombre del llama_ftype declarado en general.file_type
+fn file_type_name(ft: u64) -> Option<&'static str> {
+  Some(match ft {
+    0 => "F32", 1 => "F16", 2 => "Q4_0", 3 => "Q4_1", 7 => "Q8_0", 8 => "Q5_0", 9 => "Q5_1",
+    10 => "Q2_K", 11 => "Q3_K_S", 12 => "Q3_K_M", 13 => "Q3_K_L", 14 => "Q4_K_S", 15 => "Q4_K_M",
+    16 => "Q5_K_S", 17 => "Q5_K_M", 18 => "Q6_K", 19 => "IQ2_XXS", 20 => "IQ2_XS", 21 => "Q2_K_S",
+    22 => "IQ3_XS", 23 => "IQ3_XXS", 24 => "IQ1_S", 25 => "IQ4_NL", 26 => "IQ3_S", 27 => "IQ3_M",
+    28 => "IQ2_S", 29 => "IQ2_M", 30 => "IQ4_XS", 31 => "IQ1_M", 32 => "BF16", 36 => "TQ1_0", 37 => "TQ2_0",
+    _ => return None,
+  })
+}
+
+// Lee el header y la tabla de tensores de un GGUF y valida que el archivo
+// contenga todos los datos que declara (detecta descargas truncadas).
+pub fn inspect(path: &Path) -> Result<GgufInfo, String> {
+  let file = File::open(path).map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+  let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+  parse(BufReader::new(file), file_size, path.to_string_lossy().into_owned())
+}
+
+// Los tamaños salen del header, que puede venir de cualquier USB: toda la
+// aritmética va comprobada
+fn parse<R: Read + Seek>(inner: R, file_size: u64, path: String) -> Result<GgufInfo, String> {
+  let mut r = Reader { inner, version: 0, pos: 0 };
+
+  if &r.bytes::<4>()? != GGUF_MAGIC { return Err("not a GGUF file (bad magic)".into()); }
+  r.version = r.u32()?;
+  if !(1..=3).contains(&r.version) { return Err(format!("unsupported GGUF version {}", r.version)); }
+  let tensor_count = r.len()?;
+  let kv_count = r.len()?;
+  if tensor_count > MAX_COUNT || kv_count > MAX_COUNT { return Err("GGUF header counts out of range".into()); }
+
+  let mut architecture = None;
+  let mut name = None;
+  let mut file_type = None;
+  let mut alignment = DEFAULT_ALIGNMENT;
+  let mut chat_template = None;
+  let mut adapter_type = None;
+  // Claves numéricas `<arquitectura>.*`; se resuelven al conocer la arquitectura
+  let mut arch_values: Vec<(String, u64)> = Vec::new();
+  for _ in 0..kv_count {
+    let key = r.string()?;
+    let ty = r.u32()?;
+    let value = r.value(ty)?;
+    match (key.as_str(), value) {
+      ("general.architecture", Value::Str(s)) => architecture = Some(s),
+      ("general.name", Value::Str(s)) => name = Some(s),
+      ("general.file_type", Value::Uint(v)) => file_type = Some(v),
+      ("general.file_type", Value::Int(v)) => file_type = u64::try_from(v).ok(),
+      ("general.alignment", Value::Uint(v)) if v > 0 => alignment = v,
+      ("tokenizer.chat_template", Value::Str(s)) => chat_template = Some(s),
+      ("adapter.type", Value::Str(s)) => adapter_type = Some(s),
+      (k, Value::Uint(v)) if !k.starts_with("general.") && !k.starts_with("tokenizer.") => arch_values.push((k.to_string(), v)),
+      _ => {}
+    }
+  }
+  let arch_value = |suffix: &str| architecture.as_ref().and_then(|arch| {
+    let key = format!("{}.{}", arch, suffix);
+    arch_values.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+  });
+  let context_length = arch_value("context_length");
+  let block_count = arch_value("block_count");
+  let embedding_length = arch_value("embedding_length");
+  let head_count = arch_value("attention.head_count");
+  let head_count_kv = arch_value("attention.head_count_kv").or(head_count);
+
+  let mut parameter_count: u64 = 0;
+  let mut data_end: u64 = 0;
+  let mut type_bytes: std::collections::HashMap<u32, u64> = std::collections::HashMap::new();
+  for _ in 0..tensor_count {
+    r.skip_string()?;
+    let n_dims = r.u32()?;
+    if n_dims > 8 { return Err(format!("invalid tensor rank {}", n_dims)); }
+    let mut elements: u64 = 1;
+    for _ in 0..n_dims {
+      elements = elements.checked_mul(r.len()?).ok_or("tensor dimensions overflow")?;
+    }
+    let ty = r.u32()?;
+    let offset = r.u64()?;
+    parameter_count = parameter_count.checked_add(elements).ok_or("tensor sizes overflow")?;
+    let size = match ggml_type_size(ty) {
+      Some((block, bytes)) => elements.div_ceil(block).checked_mul(bytes).ok_or("tensor sizes overflow")?,
+      None => 0,
+    };
+    let bytes = type_bytes.entry(ty).or_default();
+    *bytes = bytes.checked_add(size).ok_or("tensor sizes overflow")?;
+    data_end = data_end.max(offset.checked_add(size).ok_or("tensor sizes overflow")?);
+  }
+  let data_start = r.pos.div_ceil(alignment).checked_mul(alignment).ok_or("tensor sizes overflow")?;
+  let expected_size = data_start.checked_add(data_end).ok_or("tensor sizes overflow")?;
+  if file_size < expected_size {
+    return Err(format!("truncated GGUF: {} bytes on disk, header expects {}", file_size, expected_size));
+  }
+
+  // Cuantización declarada; si falta, el tipo que más bytes ocupa
+  let quantization = file_type.and_then(file_type_name).map(str::to_string).or_else(|| {
+    type_bytes.iter().max_by_key(|(_, bytes)| **bytes).map(|(ty, _)| ggml_type_name(*ty).to_string())
+  });
+  let bits_per_weight = if parameter_count > 0 { (data_end as f64 * 8.0) / parameter_count as f64 } else { 0.0 };
+
+  Ok(GgufInfo {
+    path,
+    file_size,
+    version: r.version,
+    architecture,
+    name,
+    parameter_count,
+    quantization,
+    context_length,
+    chat_template,
+    block_count,
+    embedding_length,
+    head_count,
+    head_count_kv,
+    tensor_count,
+    expected_size,
+    bits_per_weight,
+    adapter_type,
+  })
+}
+
+impl GgufInfo {
+  // Bytes de caché KV (f16) para `n_ctx` tokens; None si faltan datos de arquitectura
+  pub fn kv_cache_bytes(&self, n_ctx: u64) -> Option<u64> {
+    let n_layer = self.block_count?;
+    let n_embd = self.embedding_length?;
+    let n_head = self.head_count.filter(|h| *h > 0)?;
+    let n_head_kv = self.head_count_kv.unwrap_or(n_head);
+    let per_token = n_embd.checked_mul(n_head_kv)? / n_head;
+    4u64.checked_mul(n_layer)?.checked_mul(n_ctx)?.checked_mul(per_token)
+  }
+}
+
+#[tauri::command]
+pub async fn inspect_model(path: String) -> Result<GgufInfo, String> {
+  tauri::async_runtime::spawn_blocking(move || inspect(Path::new(&path)))
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Cursor;
+
+  fn string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u64).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+  }
+
+  // GGUF v3 mínimo: arquitectura llama y un tensor F32 [dims] por cada entrada
+  fn gguf(tensors: &[(&[u64], u64)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(GGUF_MAGIC);
+    out.extend_from_slice(&3u32.to_le_bytes());
+    out.extend_from_slice(&(tensors.len() as u64).to_le_bytes());
+    out.extend_from_slice(&2u64.to_le_bytes());
+    string(&mut out, "general.architecture");
+    out.extend_from_slice(&8u32.to_le_bytes());
+    string(&mut out, "llama");
+    string(&mut out, "llama.block_count");
+    out.extend_from_slice(&4u32.to_le_bytes());
+    out.extend_from_slice(&2u32.to_le_bytes());
+    let mut data_end = 0u64;
+    for (i, (dims, offset)) in tensors.iter().enumerate() {
+      string(&mut out, &format!("t{}", i));
+      out.extend_from_slice(&(dims.len() as u32).to_le_bytes());
+      for d in *dims { out.extend_from_slice(&d.to_le_bytes()); }
+      out.extend_from_slice(&0u32.to_le_bytes());
+      out.extend_from_slice(&offset.to_le_bytes());
+      let size = dims.iter().product::<u64>().saturating_mul(4);
+      data_end = data_end.max(offset.saturating_add(size));
+    }
+    let data_start = (out.len() as u64).div_ceil(DEFAULT_ALIGNMENT) * DEFAULT_ALIGNMENT;
+    out.resize(data_start as usize, 0);
+    out.resize((data_start + data_end.min(1 << 20)) as usize, 0);
+    out
+  }
+
+  fn parse_bytes(bytes: Vec<u8>) -> Result<GgufInfo, String> {
+    let size = bytes.len() as u64;
+    parse(Cursor::new(bytes), size, "test.gguf".into())
+  }
+
+  #[test]
+  fn parses_valid_header() {
+    let info = parse_bytes(gguf(&[(&[4, 8], 0), (&[16], 128)])).unwrap();
+    assert_eq!(info.architecture.as_deref(), Some("llama"));
+    assert_eq!(info.block_count, Some(2));
+    assert_eq!(info.tensor_count, 2);
+    assert_eq!(info.parameter_count, 48);
+    assert_eq!(info.quantization.as_deref(), Some("F32"));
+    assert_eq!(info.expected_size, info.file_size);
+  }
+
+  #[test]
+  fn rejects_truncated_data() {
+    let mut bytes = gguf(&[(&[4, 8], 0)]);
+    bytes.truncate(bytes.len() - 1);
+    assert!(parse_bytes(bytes).unwrap_err().starts_with("truncated GGUF"));
+  }
+
+  #[test]
+  fn rejects_truncated_header() {
+    let mut bytes = gguf(&[(&[4, 8], 0)]);
+    bytes.truncate(40);
+    assert!(parse_bytes(bytes).unwrap_err().contains("unexpected end of file"));
+  }
+
+  #[test]
+  fn rejects_bad_magic() {
+    let mut bytes = gguf(&[]);
+    bytes[0] = b'X';
+    assert!(parse_bytes(bytes).is_err());
+  }
+
+  #[test]
+  fn rejects_overflowing_tensor_sizes() {
+    // Elementos que caben en u64 pero no sus bytes
+    assert_eq!(parse_bytes(gguf(&[(&[u64::MAX / 2], 0)])).unwrap_err(), "tensor sizes overflow");
+    // Offset al final del rango de u64
+    assert_eq!(parse_bytes(gguf(&[(&[4], u64::MAX - 1)])).unwrap_err(), "tensor sizes overflow");
+    // Suma de elementos entre tensores
+    assert_eq!(parse_bytes(gguf(&[(&[u64::MAX / 4], 0), (&[u64::MAX / 4], 0), (&[u64::MAX / 4], 0), (&[u64::MAX / 4], 0), (&[8], 0)])).unwrap_err(), "tensor sizes overflow");
+  }
+
+  #[test]
+  fn rejects_deeply_nested_arrays() {
+    let nested = |levels: usize| {
+      let mut out = Vec::new();
+      out.extend_from_slice(GGUF_MAGIC);
+      out.extend_from_slice(&3u32.to_le_bytes());
+      out.extend_from_slice(&0u64.to_le_bytes());
+      out.extend_from_slice(&1u64.to_le_bytes());
+      string(&mut out, "deep");
+      out.extend_from_slice(&9u32.to_le_bytes());
+      for _ in 1..levels {
+        out.extend_from_slice(&9u32.to_le_bytes());
+        out.extend_from_slice(&1u64.to_le_bytes());
+      }
+      out.extend_from_slice(&4u32.to_le_bytes());
+      out.extend_from_slice(&0u64.to_le_bytes());
+      out.resize(out.len().div_ceil(DEFAULT_ALIGNMENT as usize) * DEFAULT_ALIGNMENT as usize, 0);
+      out
+    };
+    assert!(parse_bytes(nested(MAX_ARRAY_DEPTH as usize)).is_ok());
+    // Sin tope, esto desbordaría la pila
+    assert!(parse_bytes(nested(1_000_000)).unwrap_err().contains("nested deeper"));
+  }
+
+  #[test]
+  fn kv_cache_overflow_is_none() {
+    let mut info = parse_bytes(gguf(&[(&[4], 0)])).unwrap();
+    info.embedding_length = Some(4096);
+    info.head_count = Some(32);
+    info.head_count_kv = Some(8);
+    assert_eq!(info.kv_cache_bytes(1024), Some(4 * 2 * 1024 * 1024));
+    info.block_count = Some(u64::MAX / 2);
+    assert_eq!(info.kv_cache_bytes(1024), None);
+  }
+}
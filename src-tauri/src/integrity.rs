@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+
+use crate::ollama::OllamaClient;
+use crate::ports;
+use crate::profiles;
+use crate::services::ServiceKind;
+
+// Verificación de un tag de Ollama sin cargar el modelo: el manifiesto en el
+// almacén debe apuntar al sha256 del GGUF de origen y cada blob debe existir,
+// medir lo que dice el manifiesto y tener ese digest. El hash completo de un
+// blob se recuerda (tamaño + mtime) para no releer gigas en cada arranque.
+
+const SMOKE_TEST_TIMEOUT: Duration = Duration::from_secs(300);
+const SMOKE_TEST_PROMPT: &str = "Responde solo con la palabra: listo";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntegrityStatus {
+  Ok,
+  // El almacén es nuestro y no hay manifiesto para el tag
+  Missing,
+  // El manifiesto apunta a otro GGUF distinto del esperado
+  Mismatch,
+  // Falta un blob o su contenido no coincide con su digest
+  Corrupt,
+  // Ollama externo: no sabemos dónde guarda sus blobs
+  Unverifiable,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+  pub tag: String,
+  pub status: IntegrityStatus,
+  pub manifest: Option<String>,
+  pub model_digest: Option<String>,
+  pub expected_digest: Option<String>,
+  pub problems: Vec<String>,
+  // Blobs dañados que hay que borrar para que se vuelvan a subir
+  #[serde(skip)]
+  pub corrupt_blobs: Vec<PathBuf>,
+}
+
+impl IntegrityReport {
+  pub fn usable(&self) -> bool {
+    matches!(self.status, IntegrityStatus::Ok | IntegrityStatus::Unverifiable)
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmokeTest {
+  pub tag: String,
+  pub ok: bool,
+  pub latency_ms: u64,
+  pub reply: Option<String>,
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct VerifiedBlob {
+  size: u64,
+  modified: u64,
+}
+
+#[derive(serde::Deserialize)]
+struct Layer {
+  #[serde(rename = "mediaType", default)]
+  media_type: String,
+  digest: String,
+  #[serde(default)]
+  size: u64,
+}
+
+#[derive(serde::Deserialize)]
+struct Manifest {
+  config: Option<Layer>,
+  #[serde(default)]
+  layers: Vec<Layer>,
+}
+
+// Almacén del Ollama activo: ollama-store si lo lanzamos nosotros, si no el
+// OLLAMA_MODELS del entorno o ~/.ollama/models. El bool indica si es nuestro.
+fn store_root(app: &tauri::AppHandle) -> Option<(PathBuf, bool)> {
+  if ports::get(ServiceKind::Ollama).map(|e| e.owned).unwrap_or(true) {
+    return crate::app_data_dir(app).map(|d| (d.join("ollama-store"), true));
+  }
+  if let Ok(dir) = std::env::var("OLLAMA_MODELS") { return Some((PathBuf::from(dir), false)); }
+  std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))
+    .map(|home| (PathBuf::from(home).join(".ollama").join("models"), false))
+}
+
+// "modelo[:tag]" -> manifests/<host>/<namespace>/<modelo>/<tag>
+fn manifest_path(store: &Path, tag: &str) -> PathBuf {
+  let (name, version) = match tag.rsplit_once(':') {
+    Some((n, v)) if !v.contains('/') => (n, v),
+    _ => (tag, "latest"),
+  };
+  let parts: Vec<&str> = name.split('/').collect();
+  let (host, namespace, model) = match parts.as_slice() {
+    [host, namespace, model] => (*host, *namespace, *model),
+    [namespace, model] => ("registry.ollama.ai", *namespace, *model),
+    _ => ("registry.ollama.ai", "library", name),
+  };
+  store.join("manifests").join(host).join(namespace).join(model).join(version)
+}
+
+fn cache_path(app: &tauri::AppHandle) -> Option<PathBuf> {
+  crate::app_data_dir(app).map(|d| d.join("blob-integrity.json"))
+}
+
+fn blob_stamp(path: &Path) -> Option<VerifiedBlob> {
+  let meta = std::fs::metadata(path).ok()?;
+  let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+  Some(VerifiedBlob { size: meta.len(), modified })
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+  let mut file = File::open(path).map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+  let mut hasher = Sha256::new();
+  let mut buf = vec![0u8; 1024 * 1024];
+  loop {
+    let n = file.read(&mut buf).map_err(|e| format!("read error on {}: {}", path.display(), e))?;
+    if n == 0 { break; }
+    hasher.update(&buf[..n]);
+  }
+  Ok(hex::encode(hasher.finalize()))
+}
+
+// Compara manifiesto y blobs de `tag` con el sha256 del GGUF de origen
+pub fn check(app: &tauri::AppHandle, tag: &str, expected: Option<&str>) -> IntegrityReport {
+  let mut report = IntegrityReport {
+    tag: tag.to_string(),
+    status: IntegrityStatus::Ok,
+    manifest: None,
+    model_digest: None,
+    expected_digest: expected.map(|e| format!("sha256:{}", e)),
+    problems: vec![],
+    corrupt_blobs: vec![],
+  };
+  let Some((store, owned)) = store_root(app) else {
+    report.status = IntegrityStatus::Unverifiable;
+    return report;
+  };
+  let path = manifest_path(&store, tag);
+  report.manifest = Some(path.to_string_lossy().into_owned());
+  let manifest = match std::fs::read(&path) {
+    Ok(bytes) => match serde_json::from_slice::<Manifest>(&bytes) {
+      Ok(m) => m,
+      Err(e) => {
+        report.status = IntegrityStatus::Corrupt;
+        report.problems.push(format!("manifiesto ilegible: {}", e));
+        return report;
+      }
+    },
+    Err(_) => {
+      report.status = if owned { IntegrityStatus::Missing } else { IntegrityStatus::Unverifiable };
+      report.problems.push("manifiesto no encontrado".into());
+      return report;
+    }
+  };
+
+  let cache_file = cache_path(app);
+  let mut cache: HashMap<String, VerifiedBlob> = cache_file.as_ref()
+    .and_then(|p| std::fs::read(p).ok())
+    .and_then(|b| serde_json::from_slice(&b).ok())
+    .unwrap_or_default();
+  let mut cache_changed = false;
+
+  for layer in manifest.config.iter().chain(manifest.layers.iter()) {
+    let Some(hex) = layer.digest.strip_prefix("sha256:") else {
+      report.problems.push(format!("digest no soportado: {}", layer.digest));
+      report.status = IntegrityStatus::Corrupt;
+      continue;
+    };
+    if layer.media_type.ends_with(".model") { report.model_digest = Some(layer.digest.clone()); }
+    let blob = store.join("blobs").join(format!("sha256-{}", hex));
+    let Some(stamp) = blob_stamp(&blob) else {
+      report.problems.push(format!("falta el blob {}", layer.digest));
+      report.status = IntegrityStatus::Corrupt;
+      continue;
+    };
+    if layer.size > 0 && stamp.size != layer.size {
+      report.problems.push(format!("blob {} mide {} bytes, el manifiesto dice {}", layer.digest, stamp.size, layer.size));
+      report.status = IntegrityStatus::Corrupt;
+      report.corrupt_blobs.push(blob);
+      continue;
+    }
+    if cache.get(hex) == Some(&stamp) { continue; }
+    match sha256_file(&blob) {
+      Ok(actual) if actual == hex => { cache.insert(hex.to_string(), stamp); cache_changed = true; }
+      Ok(actual) => {
+        report.problems.push(format!("blob {} tiene sha256:{}", layer.digest, actual));
+        report.status = IntegrityStatus::Corrupt;
+        report.corrupt_blobs.push(blob);
+      }
+      Err(e) => {
+        report.problems.push(e);
+        report.status = IntegrityStatus::Corrupt;
+      }
+    }
+  }
+
+  if report.status == IntegrityStatus::Ok {
+    match (&report.model_digest, &report.expected_digest) {
+      (None, _) => {
+        report.problems.push("el manifiesto no tiene capa de modelo".into());
+        report.status = IntegrityStatus::Corrupt;
+      }
+      (Some(actual), Some(expected)) if actual != expected => {
+        report.problems.push(format!("el tag usa {} y el GGUF de origen es {}", actual, expected));
+        report.status = IntegrityStatus::Mismatch;
+      }
+      _ => {}
+    }
+  }
+  if cache_changed {
+    if let (Some(path), Ok(bytes)) = (cache_file, serde_json::to_vec(&cache)) { let _ = std::fs::write(path, bytes); }
+  }
+  report
+}
+
+// check() fuera del hilo async: puede hashear blobs de varios GB
+pub async fn verify(app: &tauri::AppHandle, tag: &str, expected: Option<String>) -> Result<IntegrityReport, String> {
+  let (app, tag) = (app.clone(), tag.to_string());
+  tauri::async_runtime::spawn_blocking(move || check(&app, &tag, expected.as_deref())).await.map_err(|e| e.to_string())
+}
+
+// Borra los blobs dañados para que la próxima importación los suba de nuevo
+pub fn discard_corrupt(report: &IntegrityReport) {
+  for blob in &report.corrupt_blobs { let _ = std::fs::remove_file(blob); }
+}
+
+#[tauri::command]
+pub async fn verify_ollama_model(tag: String, app: tauri::AppHandle) -> Result<IntegrityReport, String> {
+  let expected = profiles::applied(&app, &tag).map(|a| a.gguf_sha256);
+  verify(&app, &tag, expected).await
+}
+
+// Generación mínima con el modelo; solo a pedido del usuario (carga el modelo completo)
+#[tauri::command]
+pub async fn smoke_test_ollama_model(tag: String) -> Result<SmokeTest, String> {
+  let client = OllamaClient::from_state()?;
+  let started = Instant::now();
+  let result = client.chat_once(&tag, SMOKE_TEST_PROMPT, SMOKE_TEST_TIMEOUT).await;
+  let latency_ms = started.elapsed().as_millis() as u64;
+  Ok(match result {
+    Ok(reply) => SmokeTest { tag, ok: !reply.trim().is_empty(), latency_ms, reply: Some(reply), error: None },
+    Err(e) => SmokeTest { tag, ok: false, latency_ms, reply: None, error: Some(e.to_string()) },
+  })
+}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use axum::extract::{Path as UrlPath, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use sha2::{Digest, Sha256};
+use tokio::sync::oneshot;
+
+use crate::downloads;
+use crate::settings;
+
+// Intercambio de modelos entre equipos de la finca: cada instancia con el
+// servicio activado anuncia sus modelos por mDNS y los sirve por HTTP en
+// bloques con sha256 propio. Quien descarga verifica cada bloque y, al final,
+// el sha256 completo contra el esperado (catálogo firmado), así que un equipo
+// de la LAN nunca es fuente de confianza por sí mismo.
+
+const SERVICE_TYPE: &str = "_ganado-models._tcp.local.";
+const PROTOCOL_VERSION: &str = "1";
+const DEFAULT_PORT: u16 = 47631;
+const CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+// Tiempo que se escucha mDNS para reunir equipos antes de descargar
+const DISCOVERY_WINDOW: Duration = Duration::from_millis(2500);
+// Las descargas reutilizan el último sondeo mDNS durante este tiempo
+const PEER_CACHE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SharedModel {
+  pub file_name: String,
+  pub sha256: String,
+  pub size: u64,
+}
+
+// sha256 de cada bloque de CHUNK_SIZE bytes (el último puede ser menor)
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ChunkManifest {
+  pub sha256: String,
+  pub size: u64,
+  pub chunk_size: u64,
+  pub chunks: Vec<String>,
+}
+
+impl ChunkManifest {
+  pub fn chunk_len(&self, index: u64) -> u64 {
+    let start = index * self.chunk_size;
+    self.size.saturating_sub(start).min(self.chunk_size)
+  }
+}
+
+// Manifiesto en caché junto con el estado del archivo del que salió
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CachedManifest {
+  modified: u64,
+  manifest: ChunkManifest,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LanPeer {
+  pub name: String,
+  pub address: String,
+  pub models: Vec<SharedModel>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LanStatus {
+  pub enabled: bool,
+  pub port: Option<u16>,
+  pub instance: Option<String>,
+  pub shared: Vec<SharedModel>,
+}
+
+// Equipo de la LAN que tiene el modelo buscado, con su manifiesto ya validado
+#[derive(Clone, Debug)]
+pub struct PeerSource {
+  pub peer: String,
+  pub base_url: String,
+  pub manifest: ChunkManifest,
+}
+
+struct LanService {
+  port: u16,
+  instance: String,
+  fullname: String,
+  daemon: ServiceDaemon,
+  shutdown: oneshot::Sender<()>,
+}
+
+static LAN_SERVICE: tauri::async_runtime::Mutex<Option<LanService>> = tauri::async_runtime::Mutex::const_new(None);
+// Evita calcular dos veces el mismo manifiesto si varios equipos piden a la vez
+static MANIFEST_LOCK: tauri::async_runtime::Mutex<()> = tauri::async_runtime::Mutex::const_new(());
+// (nombre, dirección) de cada equipo que anuncia el servicio
+type Discovered = Vec<(String, SocketAddr)>;
+// Último resultado de discover() y cuándo se obtuvo
+static PEER_CACHE: std::sync::Mutex<Option<(Instant, Discovered)>> = std::sync::Mutex::new(None);
+
+struct ServerState {
+  models_dir: PathBuf,
+  cache_dir: PathBuf,
+}
+
+fn configured_port(app: &tauri::AppHandle) -> u16 {
+  std::env::var("GANADO_LAN_PORT").ok().and_then(|s| s.parse::<u16>().ok())
+    .or(settings::load(app).lan_port)
+    .unwrap_or(DEFAULT_PORT)
+}
+
+fn modified_secs(path: &Path) -> u64 {
+  std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+fn gguf_files(models_dir: &Path) -> Vec<PathBuf> {
+  let mut files: Vec<PathBuf> = std::fs::read_dir(models_dir).map(|rd| {
+    rd.flatten().map(|e| e.path())
+      .filter(|p| p.extension().and_then(|s| s.to_str()).unwrap_or("") == "gguf")
+      .collect()
+  }).unwrap_or_default();
+  files.sort();
+  files
+}
+
+// Modelos instalados con sha256 conocido: el del sidecar .sha256 o, para los
+// que nunca se verificaron, el que calculó index_models en la caché de lan/
+fn shared_models(models_dir: &Path, cache_dir: &Path) -> Vec<(SharedModel, PathBuf)> {
+  gguf_files(models_dir).into_iter().filter_map(|path| {
+    let sha256 = downloads::read_digest_sidecar(&path).or_else(|| cached_manifest(cache_dir, &path).map(|m| m.sha256))?;
+    let size = std::fs::metadata(&path).ok()?.len();
+    let file_name = path.file_name()?.to_string_lossy().into_owned();
+    Some((SharedModel { file_name, sha256, size }, path))
+  }).collect()
+}
+
+// Recorre el archivo una vez calculando el sha256 total y el de cada bloque
+fn build_manifest(path: &Path) -> Result<ChunkManifest, String> {
+  let mut file = File::open(path).map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+  let size = file.metadata().map_err(|e| e.to_string())?.len();
+  let mut total = Sha256::new();
+  let mut chunks = Vec::new();
+  let mut buf = vec![0u8; 1024 * 1024];
+  let mut read: u64 = 0;
+  while read < size {
+    let mut chunk = Sha256::new();
+    let mut in_chunk: u64 = 0;
+    while in_chunk < CHUNK_SIZE {
+      let want = (CHUNK_SIZE - in_chunk).min(buf.len() as u64) as usize;
+      let n = file.read(&mut buf[..want]).map_err(|e| e.to_string())?;
+      if n == 0 { break; }
+      chunk.update(&buf[..n]);
+      total.update(&buf[..n]);
+      in_chunk += n as u64;
+    }
+    if in_chunk == 0 { break; }
+    read += in_chunk;
+    chunks.push(hex::encode(chunk.finalize()));
+  }
+  if read != size { return Err(format!("{} changed while hashing", path.display())); }
+  Ok(ChunkManifest { sha256: hex::encode(total.finalize()), size, chunk_size: CHUNK_SIZE, chunks })
+}
+
+fn cache_path(cache_dir: &Path, model: &Path) -> PathBuf {
+  cache_dir.join(format!("{}.chunks.json", model.file_name().unwrap_or_default().to_string_lossy()))
+}
+
+// Manifiesto de la caché si el archivo no cambió desde que se calculó
+fn cached_manifest(cache_dir: &Path, model: &Path) -> Option<ChunkManifest> {
+  let size = std::fs::metadata(model).ok()?.len();
+  let cached: CachedManifest = serde_json::from_slice(&std::fs::read(cache_path(cache_dir, model)).ok()?).ok()?;
+  let fresh = cached.modified == modified_secs(model) && cached.manifest.size == size && cached.manifest.chunk_size == CHUNK_SIZE;
+  if fresh { Some(cached.manifest) } else { None }
+}
+
+// Manifiesto desde la caché si el archivo no cambió; si no, se recalcula
+fn load_manifest(cache_dir: &Path, model: &Path) -> Result<ChunkManifest, String> {
+  if let Some(manifest) = cached_manifest(cache_dir, model) { return Ok(manifest); }
+  let cache = cache_path(cache_dir, model);
+  let modified = modified_secs(model);
+  let manifest = build_manifest(model)?;
+  let _ = std::fs::create_dir_all(cache_dir);
+  if let Ok(bytes) = serde_json::to_vec(&CachedManifest { modified, manifest: manifest.clone() }) {
+    let _ = std::fs::write(&cache, bytes);
+  }
+  Ok(manifest)
+}
+
+// Los modelos sin sidecar (p. ej. los copiados desde Resources) se hashean una
+// vez al activar el servicio para poder compartirlos. El hash queda solo en la
+// caché de lan/: un sidecar los haría pasar por verificados en el resto de la app.
+fn index_models(models_dir: &Path, cache_dir: &Path) {
+  for path in gguf_files(models_dir) {
+    if downloads::read_digest_sidecar(&path).is_some() { continue; }
+    let _ = load_manifest(cache_dir, &path);
+  }
+}
+
+fn read_chunk(path: &Path, manifest: &ChunkManifest, index: u64) -> Result<Vec<u8>, String> {
+  let mut file = File::open(path).map_err(|e| e.to_string())?;
+  file.seek(SeekFrom::Start(index * manifest.chunk_size)).map_err(|e| e.to_string())?;
+  let mut bytes = vec![0u8; manifest.chunk_len(index) as usize];
+  file.read_exact(&mut bytes).map_err(|e| e.to_string())?;
+  Ok(bytes)
+}
+
+type HttpError = (StatusCode, String);
+
+async fn manifest_for(state: &ServerState, sha256: &str) -> Result<(ChunkManifest, PathBuf), HttpError> {
+  let sha256 = sha256.to_lowercase();
+  let (_, path) = shared_models(&state.models_dir, &state.cache_dir).into_iter()
+    .find(|(m, _)| m.sha256 == sha256)
+    .ok_or((StatusCode::NOT_FOUND, format!("model {} not shared", sha256)))?;
+  let _guard = MANIFEST_LOCK.lock().await;
+  let (cache_dir, model) = (state.cache_dir.clone(), path.clone());
+  let manifest = tauri::async_runtime::spawn_blocking(move || load_manifest(&cache_dir, &model)).await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+  // Un archivo que ya no coincide con su sha256 registrado no se comparte
+  if manifest.sha256 != sha256 {
+    return Err((StatusCode::CONFLICT, format!("{} no longer matches its sha256", path.display())));
+  }
+  Ok((manifest, path))
+}
+
+async fn list_handler(State(state): State<Arc<ServerState>>) -> Json<Vec<SharedModel>> {
+  Json(shared_models(&state.models_dir, &state.cache_dir).into_iter().map(|(m, _)| m).collect())
+}
+
+async fn manifest_handler(State(state): State<Arc<ServerState>>, UrlPath(sha256): UrlPath<String>) -> Result<Json<ChunkManifest>, HttpError> {
+  manifest_for(&state, &sha256).await.map(|(m, _)| Json(m))
+}
+
+async fn chunk_handler(State(state): State<Arc<ServerState>>, UrlPath((sha256, index)): UrlPath<(String, u64)>) -> Result<Response, HttpError> {
+  let (manifest, path) = manifest_for(&state, &sha256).await?;
+  let expected = manifest.chunks.get(index as usize).cloned()
+    .ok_or((StatusCode::RANGE_NOT_SATISFIABLE, format!("chunk {} out of range", index)))?;
+  let bytes = tauri::async_runtime::spawn_blocking(move || read_chunk(&path, &manifest, index)).await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+  Ok(([(header::CONTENT_TYPE, "application/octet-stream".to_string()), (header::HeaderName::from_static("x-chunk-sha256"), expected)], bytes).into_response())
+}
+
+async fn status(app: &tauri::AppHandle) -> LanStatus {
+  let guard = LAN_SERVICE.lock().await;
+  let shared = crate::app_data_dir(app)
+    .map(|dir| shared_models(&dir.join("models"), &dir.join("lan")).into_iter().map(|(m, _)| m).collect())
+    .unwrap_or_default();
+  LanStatus {
+    enabled: guard.is_some(),
+    port: guard.as_ref().map(|s| s.port),
+    instance: guard.as_ref().map(|s| s.instance.clone()),
+    shared,
+  }
+}
+
+pub async fn start(app: &tauri::AppHandle) -> Result<(), String> {
+  let mut guard = LAN_SERVICE.lock().await;
+  if guard.is_some() { return Ok(()); }
+  let data_dir = crate::app_data_dir(app).ok_or("app_data_dir not found")?;
+  let state = Arc::new(ServerState { models_dir: data_dir.join("models"), cache_dir: data_dir.join("lan") });
+  let port = configured_port(app);
+
+  let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await
+    .map_err(|e| format!("cannot listen on port {}: {}", port, e))?;
+  let router = Router::new()
+    .route("/lan/v1/models", get(list_handler))
+    .route("/lan/v1/models/:sha256", get(manifest_handler))
+    .route("/lan/v1/models/:sha256/chunks/:index", get(chunk_handler))
+    .with_state(state.clone());
+  let (shutdown, stop) = oneshot::channel::<()>();
+  tauri::async_runtime::spawn(async move {
+    let _ = axum::serve(listener, router).with_graceful_shutdown(async { let _ = stop.await; }).await;
+  });
+
+  let instance = format!("ganado-{:08x}", rand::random::<u32>());
+  let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+  let info = ServiceInfo::new(SERVICE_TYPE, &instance, &format!("{}.local.", instance), (), port, &[("v", PROTOCOL_VERSION)][..])
+    .map_err(|e| e.to_string())?
+    .enable_addr_auto();
+  let fullname = info.get_fullname().to_string();
+  daemon.register(info).map_err(|e| e.to_string())?;
+
+  tauri::async_runtime::spawn_blocking(move || index_models(&state.models_dir, &state.cache_dir));
+  crate::boot_log(app, format!("[tauri] compartiendo modelos en la LAN como {} (puerto {})", instance, port)).await;
+  *guard = Some(LanService { port, instance, fullname, daemon, shutdown });
+  Ok(())
+}
+
+pub async fn stop(app: &tauri::AppHandle) {
+  let service = LAN_SERVICE.lock().await.take();
+  if let Some(service) = service {
+    let _ = service.daemon.unregister(&service.fullname);
+    let _ = service.daemon.shutdown();
+    let _ = service.shutdown.send(());
+    crate::boot_log(app, "[tauri] servicio LAN detenido").await;
+  }
+}
+
+// Escucha mDNS durante DISCOVERY_WINDOW y devuelve (nombre, dirección) de
+// cada equipo que anuncia el servicio, excluyendo esta misma instancia
+async fn discover() -> Discovered {
+  let own = LAN_SERVICE.lock().await.as_ref().map(|s| s.fullname.clone());
+  let daemon = match ServiceDaemon::new() { Ok(d) => d, Err(_) => return Vec::new() };
+  let events = match daemon.browse(SERVICE_TYPE) { Ok(rx) => rx, Err(_) => { let _ = daemon.shutdown(); return Vec::new(); } };
+  let deadline = tokio::time::Instant::now() + DISCOVERY_WINDOW;
+  let mut peers: HashMap<String, SocketAddr> = HashMap::new();
+  while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, events.recv_async()).await {
+    if let ServiceEvent::ServiceResolved(info) = event {
+      if Some(info.get_fullname()) == own.as_deref() { continue; }
+      if info.get_property_val_str("v") != Some(PROTOCOL_VERSION) { continue; }
+      // IPv4 primero: las IPv6 link-local no sirven sin scope en la URL
+      let mut addrs: Vec<&IpAddr> = info.get_addresses().iter().collect();
+      addrs.sort_by_key(|ip| !ip.is_ipv4());
+      if let Some(ip) = addrs.first() {
+        peers.insert(info.get_fullname().to_string(), SocketAddr::new(**ip, info.get_port()));
+      }
+    }
+  }
+  let _ = daemon.shutdown();
+  let peers: Discovered = peers.into_iter().collect();
+  *PEER_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), peers.clone()));
+  peers
+}
+
+// discover() con caché de PEER_CACHE_TTL, para no sondear la red en cada descarga
+async fn discover_cached() -> Discovered {
+  let cached = PEER_CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_ref()
+    .filter(|(at, _)| at.elapsed() < PEER_CACHE_TTL)
+    .map(|(_, peers)| peers.clone());
+  match cached {
+    Some(peers) => peers,
+    None => discover().await,
+  }
+}
+
+pub fn peer_client() -> Result<reqwest::Client, String> {
+  reqwest::Client::builder()
+    .connect_timeout(Duration::from_secs(3))
+    .read_timeout(Duration::from_secs(30))
+    .build()
+    .map_err(|e| e.to_string())
+}
+
+async fn peer_models(client: &reqwest::Client, addr: &SocketAddr) -> Result<Vec<SharedModel>, String> {
+  let res = client.get(format!("http://{}/lan/v1/models", addr)).send().await.map_err(|e| e.to_string())?;
+  if !res.status().is_success() { return Err(format!("HTTP {}", res.status())); }
+  res.json().await.map_err(|e| e.to_string())
+}
+
+async fn list_peers(fresh: bool) -> Result<Vec<LanPeer>, String> {
+  let client = peer_client()?;
+  let found = if fresh { discover().await } else { discover_cached().await };
+  let lookups = found.iter().map(|(name, addr)| {
+    let client = &client;
+    async move {
+      let models = peer_models(client, addr).await.unwrap_or_default();
+      LanPeer { name: name.clone(), address: addr.to_string(), models }
+    }
+  });
+  Ok(futures_util::future::join_all(lookups).await)
+}
+
+// Equipos de la LAN que comparten el modelo con este sha256, con manifiesto
+// coherente con lo esperado. Vacío si no hay ninguno (o falla mDNS) y, sin
+// lan_sharing activado, ni siquiera se sondea la red.
+pub async fn find_model_sources(app: &tauri::AppHandle, sha256: &str) -> Vec<PeerSource> {
+  if !settings::load(app).lan_sharing { return Vec::new(); }
+  let sha256 = sha256.to_lowercase();
+  let client = match peer_client() { Ok(c) => c, Err(_) => return Vec::new() };
+  let mut sources = Vec::new();
+  for peer in list_peers(false).await.unwrap_or_default() {
+    if !peer.models.iter().any(|m| m.sha256 == sha256) { continue; }
+    let base_url = format!("http://{}/lan/v1/models/{}", peer.address, sha256);
+    let manifest: ChunkManifest = match client.get(&base_url).send().await {
+      Ok(res) if res.status().is_success() => match res.json().await { Ok(m) => m, Err(_) => continue },
+      _ => continue,
+    };
+    let expected_chunks = manifest.size.div_ceil(manifest.chunk_size.max(1));
+    if manifest.sha256 != sha256 || manifest.chunk_size == 0 || manifest.chunks.len() as u64 != expected_chunks { continue; }
+    sources.push(PeerSource { peer: peer.name, base_url, manifest });
+  }
+  sources
+}
+
+// Baja un bloque y comprueba su longitud y sha256 contra el manifiesto
+pub async fn fetch_chunk(client: &reqwest::Client, source: &PeerSource, index: u64) -> Result<Vec<u8>, String> {
+  let expected = source.manifest.chunks.get(index as usize).ok_or_else(|| format!("chunk {} out of range", index))?;
+  let res = client.get(format!("{}/chunks/{}", source.base_url, index)).send().await.map_err(|e| e.to_string())?;
+  if !res.status().is_success() { return Err(format!("HTTP {}", res.status())); }
+  let bytes = res.bytes().await.map_err(|e| e.to_string())?;
+  if bytes.len() as u64 != source.manifest.chunk_len(index) {
+    return Err(format!("chunk {} has {} bytes, expected {}", index, bytes.len(), source.manifest.chunk_len(index)));
+  }
+  let digest = hex::encode(Sha256::digest(&bytes));
+  if &digest != expected { return Err(format!("chunk {} sha256 mismatch", index)); }
+  Ok(bytes.to_vec())
+}
+
+#[tauri::command]
+pub async fn set_lan_sharing(enabled: bool, port: Option<u16>, app: tauri::AppHandle) -> Result<LanStatus, String> {
+  let previous = settings::load(&app);
+  settings::update(&app, |s| {
+    s.lan_sharing = enabled;
+    if port.is_some() { s.lan_port = port; }
+  })?;
+  // Un cambio de puerto requiere reiniciar el servicio
+  if !enabled || (port.is_some() && port != previous.lan_port) {
+    stop(&app).await;
+  }
+  if enabled { start(&app).await?; }
+  Ok(status(&app).await)
+}
+
+#[tauri::command]
+pub async fn lan_sharing_status(app: tauri::AppHandle) -> LanStatus {
+  status(&app).await
+}
+
+#[tauri::command]
+pub async fn list_lan_peers() -> Result<Vec<LanPeer>, String> {
+  list_peers(true).await
+}
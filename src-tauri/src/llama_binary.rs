@@ -0,0 +1,380 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use crate::cpu;
+use crate::net;
+use crate::storage;
+
+// Instalación de llama-server a partir de un manifiesto fijado por versión
+// (llama/manifest.json, embebido al compilar). Cada versión se extrae en
+// app_data/bin/llama/<versión>/ y active.json apunta a la actual y a la
+// anterior, que se conserva para poder volver atrás.
+
+const MANIFEST_JSON: &str = include_str!("../llama/manifest.json");
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct LlamaAsset {
+  pub os: String,
+  pub arch: String,
+  // Nivel de CPU para el que está compilada (ver cpu::supports); None = genérica
+  #[serde(default)]
+  pub cpu: Option<String>,
+  pub url: String,
+  #[serde(default)]
+  pub mirrors: Vec<String>,
+  #[serde(default)]
+  pub sha256: String,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct LlamaManifest {
+  pub version: String,
+  pub assets: Vec<LlamaAsset>,
+  // Plataformas sin build oficial, con el motivo que se muestra al usuario
+  #[serde(default)]
+  pub unsupported: Vec<UnsupportedPlatform>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct UnsupportedPlatform {
+  pub os: String,
+  pub arch: String,
+  pub reason: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct InstalledLlama {
+  pub version: String,
+  // Lo que imprime `llama-server --version` (p. ej. "4600 (a1b2c3d)")
+  pub reported_version: Option<String>,
+  #[serde(default)]
+  pub variant: Option<String>,
+  pub binary: String,
+  pub sha256: String,
+  pub installed_at: u64,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+struct ActiveFile {
+  current: Option<InstalledLlama>,
+  previous: Option<InstalledLlama>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LlamaBinaryStatus {
+  pub pinned_version: Option<String>,
+  pub current: Option<InstalledLlama>,
+  pub previous: Option<InstalledLlama>,
+  // Binario suelto de versiones anteriores de la app (bin/llama-server)
+  pub legacy: Option<String>,
+}
+
+// Una sola instalación/actualización a la vez
+static INSTALL_LOCK: tauri::async_runtime::Mutex<()> = tauri::async_runtime::Mutex::const_new(());
+
+fn binary_name() -> &'static str {
+  if cfg!(target_os = "windows") { "llama-server.exe" } else { "llama-server" }
+}
+
+fn llama_root(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  Ok(crate::app_data_dir(app).ok_or("app_data_dir not found")?.join("bin").join("llama"))
+}
+
+fn read_active(root: &Path) -> ActiveFile {
+  std::fs::read(root.join("active.json")).ok()
+    .and_then(|b| serde_json::from_slice(&b).ok())
+    .unwrap_or_default()
+}
+
+// El cambio de versión es el rename de active.json: o se ve la anterior o la nueva
+fn write_active(root: &Path, active: &ActiveFile) -> Result<(), String> {
+  let tmp = root.join("active.json.tmp");
+  let bytes = serde_json::to_vec_pretty(active).map_err(|e| e.to_string())?;
+  let mut f = File::create(&tmp).map_err(|e| e.to_string())?;
+  f.write_all(&bytes).map_err(|e| e.to_string())?;
+  f.sync_all().map_err(|e| e.to_string())?;
+  std::fs::rename(&tmp, root.join("active.json")).map_err(|e| e.to_string())
+}
+
+pub fn manifest() -> Result<LlamaManifest, String> {
+  serde_json::from_str(MANIFEST_JSON).map_err(|e| format!("invalid llama manifest: {}", e))
+}
+
+// Versión y archivo a instalar en este equipo. LLAMA_BINARY_URL permite una
+// build propia pero exige LLAMA_BINARY_SHA256; nunca se instala sin hash.
+pub fn pinned_asset() -> Result<(String, LlamaAsset), String> {
+  let (os, arch) = (std::env::consts::OS, std::env::consts::ARCH);
+  if let Ok(url) = std::env::var("LLAMA_BINARY_URL") {
+    let sha256 = std::env::var("LLAMA_BINARY_SHA256").map_err(|_| "LLAMA_BINARY_URL requires LLAMA_BINARY_SHA256".to_string())?;
+    // Mirrors adicionales (CDN propio, host en la LAN) separados por coma
+    let mirrors: Vec<String> = std::env::var("LLAMA_BINARY_MIRRORS").ok()
+      .map(|v| v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+      .unwrap_or_default();
+    let version = format!("custom-{}", &sha256.get(..12).unwrap_or(&sha256));
+    return Ok((version, LlamaAsset { os: os.into(), arch: arch.into(), cpu: None, url, mirrors, sha256: sha256.to_lowercase() }));
+  }
+  let manifest = manifest()?;
+  if let Some(u) = manifest.unsupported.iter().find(|u| u.os == os && u.arch == arch) {
+    return Err(format!("llama-server is not supported on {}/{}: {}", os, arch, u.reason));
+  }
+  let for_platform: Vec<LlamaAsset> = manifest.assets.into_iter().filter(|a| a.os == os && a.arch == arch).collect();
+  if for_platform.is_empty() { return Err(format!("no llama-server build pinned for {}/{}", os, arch)); }
+  // La variante más optimizada que esta CPU puede ejecutar
+  let features = cpu::features();
+  let level = |a: &LlamaAsset| a.cpu.clone().unwrap_or_else(|| "generic".into());
+  let asset = for_platform.iter()
+    .filter(|a| cpu::supports(&features, &level(a)))
+    .max_by_key(|a| cpu::rank(&level(a)))
+    .cloned()
+    .ok_or_else(|| {
+      let builds: Vec<String> = for_platform.iter().map(level).collect();
+      format!("no llama-server build for this CPU ({}); pinned builds need {}", cpu::best_level(&features), builds.join(", "))
+    })?;
+  if asset.sha256.len() != 64 {
+    return Err(format!("llama-server {} for {}/{} ({}) has no pinned sha256; run scripts/pin-llama-release.mjs", manifest.version, os, arch, level(&asset)));
+  }
+  Ok((manifest.version, asset))
+}
+
+// Entorno con el que se ejecuta el binario: las librerías compartidas del
+// release viven junto a él
+pub fn runtime_env(bin: &Path) -> (Vec<(String, String)>, Option<PathBuf>) {
+  #[allow(unused_mut)]
+  let mut envs = Vec::new();
+  let dir = bin.parent().map(Path::to_path_buf);
+  #[cfg(target_os = "linux")]
+  if let Some(dir) = &dir {
+    let mut paths = vec![dir.clone()];
+    if let Some(existing) = std::env::var_os("LD_LIBRARY_PATH") { paths.extend(std::env::split_paths(&existing)); }
+    if let Ok(joined) = std::env::join_paths(paths) { envs.push(("LD_LIBRARY_PATH".to_string(), joined.to_string_lossy().into_owned())); }
+  }
+  (envs, dir)
+}
+
+// Comando para lanzar el binario con sus bibliotecas compartidas al lado
+pub fn command(bin: &Path) -> Command {
+  let mut cmd = Command::new(bin);
+  let (envs, dir) = runtime_env(bin);
+  cmd.envs(envs);
+  if let Some(dir) = dir { cmd.current_dir(dir); }
+  cmd
+}
+
+// Ejecuta `--version` y devuelve la línea "version: ..." si el binario arranca
+pub fn detect_version(bin: &Path) -> Result<Option<String>, String> {
+  let output = command(bin).arg("--version").output().map_err(|e| format!("cannot run {}: {}", bin.display(), e))?;
+  let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+  let version = text.lines()
+    .find_map(|l| l.trim().strip_prefix("version:").map(|v| v.trim().to_string()));
+  if !output.status.success() && version.is_none() {
+    return Err(format!("{} --version failed: {}", bin.display(), text.trim()));
+  }
+  Ok(version)
+}
+
+// Binario a usar: la versión activa o, si no hay, el suelto de instalaciones viejas
+pub fn resolve(app: &tauri::AppHandle) -> Option<PathBuf> {
+  let root = llama_root(app).ok()?;
+  if let Some(current) = read_active(&root).current {
+    let bin = PathBuf::from(current.binary);
+    if bin.exists() { return Some(bin); }
+  }
+  let legacy = root.parent()?.join(binary_name());
+  if legacy.exists() { Some(legacy) } else { None }
+}
+
+fn find_binary(dir: &Path) -> Option<PathBuf> {
+  let rd = std::fs::read_dir(dir).ok()?;
+  let mut subdirs = Vec::new();
+  for entry in rd.flatten() {
+    let path = entry.path();
+    if path.is_dir() { subdirs.push(path); } else if path.file_name().and_then(|n| n.to_str()) == Some(binary_name()) { return Some(path); }
+  }
+  subdirs.into_iter().find_map(|d| find_binary(&d))
+}
+
+// Extrae zip o tar.gz (o copia un binario suelto) dentro de `dest`
+fn extract(archive: &Path, url: &str, dest: &Path) -> Result<(), String> {
+  std::fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+  let name = crate::downloads::file_name_from_url(url)?.to_lowercase();
+  if name.ends_with(".zip") {
+    let f = File::open(archive).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipArchive::new(f).map_err(|e| format!("invalid zip: {}", e))?;
+    // extract() descarta rutas fuera de `dest` y conserva los permisos unix
+    zip.extract(dest).map_err(|e| format!("cannot extract zip: {}", e))?;
+  } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+    let f = File::open(archive).map_err(|e| e.to_string())?;
+    tar::Archive::new(flate2::read::GzDecoder::new(f)).unpack(dest).map_err(|e| format!("cannot extract tar: {}", e))?;
+  } else {
+    std::fs::copy(archive, dest.join(binary_name())).map_err(|e| e.to_string())?;
+  }
+  Ok(())
+}
+
+// Descarga en streaming a `part` calculando el sha256
+async fn download_archive(app: &tauri::AppHandle, asset: &LlamaAsset, part: &Path) -> Result<String, String> {
+  let client = net::download_client()?;
+  let mirrors: Vec<String> = std::iter::once(asset.url.clone()).chain(asset.mirrors.iter().cloned()).collect();
+  let (res, mirror) = net::open_with_mirrors(&client, &mirrors, &net::RetryPolicy::default(), 0, None, |e| {
+    let _ = app.emit_all("download-mirror-error", e);
+  }).await.map_err(|errors| net::format_errors(&errors))?;
+  let total = res.content_length().unwrap_or(0);
+  // Archivo + extracción
+  storage::preflight(app, part.parent().unwrap_or(part), total.saturating_mul(3))?;
+  let mut writer = BufWriter::new(File::create(part).map_err(|e| e.to_string())?);
+  let mut hasher = Sha256::new();
+  let mut downloaded: u64 = 0;
+  let mut stream = res.bytes_stream();
+  while let Some(chunk) = stream.next().await {
+    let bytes = chunk.map_err(|e| format!("download interrupted: {}", e))?;
+    writer.write_all(&bytes).map_err(|e| e.to_string())?;
+    hasher.update(&bytes);
+    downloaded += bytes.len() as u64;
+    let _ = app.emit_all("llama-binary-progress", serde_json::json!({ "stage": "downloading", "downloaded": downloaded, "total": total }));
+  }
+  writer.flush().map_err(|e| e.to_string())?;
+  crate::boot_log(app, format!("[tauri] llama-server descargado desde {}", mirror)).await;
+  Ok(hex::encode(hasher.finalize()))
+}
+
+fn now_secs() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// Descarga, verifica, extrae y prueba la versión en un directorio temporal; solo
+// si todo va bien la renombra a bin/llama/<versión> y la activa. La versión que
+// estaba activa pasa a `previous` y la anterior a esa se borra.
+async fn install(app: &tauri::AppHandle, version: &str, asset: &LlamaAsset) -> Result<InstalledLlama, String> {
+  let root = llama_root(app)?;
+  std::fs::create_dir_all(&root).map_err(|e| e.to_string())?;
+  let part = root.join(format!(".download-{}", crate::downloads::file_name_from_url(&asset.url)?));
+  let digest = download_archive(app, asset, &part).await;
+  let digest = match digest {
+    Ok(d) => d,
+    Err(e) => { let _ = std::fs::remove_file(&part); return Err(e); }
+  };
+  if digest != asset.sha256.to_lowercase() {
+    let _ = std::fs::remove_file(&part);
+    return Err(format!("llama-server sha256 mismatch: got {}, expected {}", digest, asset.sha256));
+  }
+
+  let _ = app.emit_all("llama-binary-progress", serde_json::json!({ "stage": "extracting", "version": version }));
+  let staging = root.join(format!(".staging-{}", version));
+  let _ = std::fs::remove_dir_all(&staging);
+  let (archive, url, dest) = (part.clone(), asset.url.clone(), staging.clone());
+  let extracted = tauri::async_runtime::spawn_blocking(move || -> Result<(PathBuf, Option<String>), String> {
+    extract(&archive, &url, &dest)?;
+    let bin = find_binary(&dest).ok_or("llama-server not found in archive")?;
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+    }
+    let reported = detect_version(&bin)?;
+    Ok((bin, reported))
+  }).await.map_err(|e| e.to_string())?;
+  let _ = std::fs::remove_file(&part);
+  let (staged_bin, reported_version) = match extracted {
+    Ok(v) => v,
+    Err(e) => { let _ = std::fs::remove_dir_all(&staging); return Err(e); }
+  };
+
+  let mut active = read_active(&root);
+  let final_dir = root.join(version);
+  if final_dir.exists() { let _ = std::fs::remove_dir_all(&final_dir); }
+  std::fs::rename(&staging, &final_dir).map_err(|e| e.to_string())?;
+  let binary = final_dir.join(staged_bin.strip_prefix(&staging).map_err(|e| e.to_string())?);
+  let installed = InstalledLlama {
+    version: version.to_string(),
+    reported_version,
+    variant: asset.cpu.clone(),
+    binary: binary.to_string_lossy().into_owned(),
+    sha256: digest,
+    installed_at: now_secs(),
+  };
+  // Reinstalar la misma versión no desplaza a la anterior
+  let mut dropped = None;
+  if active.current.as_ref().map(|c| c.version != version).unwrap_or(false) {
+    dropped = active.previous.take();
+    active.previous = active.current.take();
+  }
+  active.current = Some(installed.clone());
+  write_active(&root, &active)?;
+  if let Some(old) = dropped {
+    if old.version != version && active.previous.as_ref().map(|p| p.version != old.version).unwrap_or(true) {
+      let _ = std::fs::remove_dir_all(root.join(&old.version));
+    }
+  }
+  crate::boot_log(app, format!("[tauri] llama-server {} instalado ({})", version, installed.reported_version.clone().unwrap_or_default())).await;
+  let _ = app.emit_all("llama-binary-progress", serde_json::json!({ "stage": "completed", "version": version }));
+  Ok(installed)
+}
+
+fn status(app: &tauri::AppHandle) -> Result<LlamaBinaryStatus, String> {
+  let root = llama_root(app)?;
+  let active = read_active(&root);
+  let legacy = root.parent().map(|p| p.join(binary_name())).filter(|p| p.exists());
+  Ok(LlamaBinaryStatus {
+    pinned_version: pinned_asset().ok().map(|(v, _)| v),
+    current: active.current,
+    previous: active.previous,
+    legacy: legacy.map(|p| p.to_string_lossy().into_owned()),
+  })
+}
+
+// Instala la versión fijada si no hay ninguna activa y devuelve la ruta del binario
+#[tauri::command]
+pub async fn download_llama_binary(app: tauri::AppHandle) -> Result<String, String> {
+  let _guard = INSTALL_LOCK.lock().await;
+  let root = llama_root(&app)?;
+  if let Some(current) = read_active(&root).current {
+    if Path::new(&current.binary).exists() { return Ok(current.binary); }
+  }
+  let (version, asset) = pinned_asset()?;
+  let f = cpu::features();
+  crate::boot_log(&app, format!("[tauri] CPU {}: se instala llama-server variante {}", cpu::best_level(&f), asset.cpu.as_deref().unwrap_or("generic"))).await;
+  install(&app, &version, &asset).await.map(|i| i.binary)
+}
+
+#[tauri::command]
+pub async fn upgrade_llama_binary(app: tauri::AppHandle) -> Result<LlamaBinaryStatus, String> {
+  let _guard = INSTALL_LOCK.lock().await;
+  let (version, asset) = pinned_asset()?;
+  let current = read_active(&llama_root(&app)?).current;
+  let up_to_date = current.as_ref()
+    .map(|c| c.version == version && c.sha256 == asset.sha256.to_lowercase() && Path::new(&c.binary).exists())
+    .unwrap_or(false);
+  if !up_to_date {
+    install(&app, &version, &asset).await?;
+  }
+  status(&app)
+}
+
+// Intercambia la versión actual con la anterior
+#[tauri::command]
+pub async fn rollback_llama_binary(app: tauri::AppHandle) -> Result<LlamaBinaryStatus, String> {
+  let _guard = INSTALL_LOCK.lock().await;
+  let root = llama_root(&app)?;
+  let mut active = read_active(&root);
+  let previous = active.previous.take().ok_or("no previous llama-server version to roll back to")?;
+  if !Path::new(&previous.binary).exists() {
+    return Err(format!("previous llama-server {} is missing", previous.version));
+  }
+  active.previous = active.current.take();
+  crate::boot_log(&app, format!("[tauri] llama-server vuelve a la versión {}", previous.version)).await;
+  active.current = Some(previous);
+  write_active(&root, &active)?;
+  status(&app)
+}
+
+#[tauri::command]
+pub fn llama_binary_status(app: tauri::AppHandle) -> Result<LlamaBinaryStatus, String> {
+  status(&app)
+}
@@ -0,0 +1,136 @@
+use std::path::Path;
+
+use crate::gguf;
+use crate::models;
+use crate::settings;
+
+// Ajustes de lanzamiento de llama-server. Cada campo en None se resuelve
+// automáticamente al arrancar según el equipo y el GGUF elegido.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LlamaServerConfig {
+  // Contexto total (se reparte entre los slots de `parallel`)
+  pub ctx_size: Option<u32>,
+  pub threads: Option<u32>,
+  pub batch_size: Option<u32>,
+  pub ubatch_size: Option<u32>,
+  pub parallel: Option<u32>,
+  // Bloquear los pesos en RAM para que el sistema no los pagine
+  pub mlock: bool,
+  // Plantilla integrada de llama.cpp (p. ej. "chatml"); None usa la del GGUF
+  pub chat_template: Option<String>,
+}
+
+// Valores concretos con los que se lanza el proceso
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ResolvedLlamaConfig {
+  pub ctx_size: u32,
+  pub threads: u32,
+  pub batch_size: u32,
+  pub ubatch_size: u32,
+  pub parallel: u32,
+  pub mlock: bool,
+  pub chat_template: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LlamaConfigView {
+  pub config: LlamaServerConfig,
+  pub effective: Option<ResolvedLlamaConfig>,
+}
+
+const DEFAULT_BATCH: u32 = 512;
+const MIN_CTX: u32 = 2048;
+// Contextos que se prueban de mayor a menor al elegir el automático
+const AUTO_CTX_STEPS: [u32; 4] = [16384, 8192, 4096, MIN_CTX];
+
+impl ResolvedLlamaConfig {
+  pub fn args(&self) -> Vec<String> {
+    let mut args = vec![
+      "--ctx-size".to_string(), self.ctx_size.to_string(),
+      "--threads".to_string(), self.threads.to_string(),
+      "--batch-size".to_string(), self.batch_size.to_string(),
+      "--ubatch-size".to_string(), self.ubatch_size.to_string(),
+      "--parallel".to_string(), self.parallel.to_string(),
+    ];
+    if self.mlock { args.push("--mlock".into()); }
+    if let Some(t) = &self.chat_template {
+      args.push("--chat-template".into());
+      args.push(t.clone());
+    }
+    args
+  }
+}
+
+// El mayor contexto (sin pasar del de entrenamiento) cuya caché KV cabe junto a
+// los pesos en la RAM disponible
+fn auto_ctx(info: &gguf::GgufInfo) -> u32 {
+  let trained = info.context_length.unwrap_or(MIN_CTX as u64).max(MIN_CTX as u64);
+  let (total, available) = models::memory_snapshot();
+  let budget = models::ram_budget(total, available);
+  let weights = models::weights_bytes(info);
+  AUTO_CTX_STEPS.iter().copied()
+    .filter(|c| *c as u64 <= trained)
+    .find(|c| {
+      let kv = info.kv_cache_bytes(*c as u64).unwrap_or(weights / 10);
+      weights + kv + models::RUNTIME_OVERHEAD <= budget
+    })
+    .unwrap_or(MIN_CTX)
+}
+
+pub fn resolve(config: &LlamaServerConfig, model: &Path) -> Result<ResolvedLlamaConfig, String> {
+  let info = gguf::inspect(model)?;
+  let cores = crate::cpu::info().physical_cores.max(1) as u32;
+  let batch_size = config.batch_size.unwrap_or(DEFAULT_BATCH);
+  Ok(ResolvedLlamaConfig {
+    ctx_size: config.ctx_size.unwrap_or_else(|| auto_ctx(&info)),
+    threads: config.threads.unwrap_or(cores),
+    batch_size,
+    ubatch_size: config.ubatch_size.unwrap_or(batch_size).min(batch_size),
+    parallel: config.parallel.unwrap_or(1),
+    mlock: config.mlock,
+    chat_template: config.chat_template.clone(),
+  })
+}
+
+fn validate(config: &LlamaServerConfig) -> Result<(), String> {
+  if let Some(c) = config.ctx_size {
+    if !(256..=262_144).contains(&c) { return Err(format!("ctx_size must be between 256 and 262144, got {}", c)); }
+  }
+  if let Some(t) = config.threads {
+    if t == 0 || t > 256 { return Err(format!("threads must be between 1 and 256, got {}", t)); }
+  }
+  for (name, value) in [("batch_size", config.batch_size), ("ubatch_size", config.ubatch_size)] {
+    if let Some(v) = value {
+      if !(32..=8192).contains(&v) { return Err(format!("{} must be between 32 and 8192, got {}", name, v)); }
+    }
+  }
+  if let Some(p) = config.parallel {
+    if p == 0 || p > 16 { return Err(format!("parallel must be between 1 and 16, got {}", p)); }
+  }
+  if let Some(t) = &config.chat_template {
+    if t.trim().is_empty() { return Err("chat_template cannot be empty".into()); }
+  }
+  Ok(())
+}
+
+fn view(config: LlamaServerConfig, model_path: Option<String>) -> Result<LlamaConfigView, String> {
+  let effective = match model_path {
+    Some(p) => Some(resolve(&config, Path::new(&p))?),
+    None => None,
+  };
+  Ok(LlamaConfigView { config, effective })
+}
+
+// Con `model_path` devuelve además los valores efectivos para ese modelo
+#[tauri::command]
+pub fn get_llama_config(model_path: Option<String>, app: tauri::AppHandle) -> Result<LlamaConfigView, String> {
+  view(settings::load(&app).llama, model_path)
+}
+
+#[tauri::command]
+pub fn set_llama_config(config: LlamaServerConfig, model_path: Option<String>, app: tauri::AppHandle) -> Result<LlamaConfigView, String> {
+  validate(&config)?;
+  let saved = settings::update(&app, |s| s.llama = config)?;
+  view(saved.llama, model_path)
+}
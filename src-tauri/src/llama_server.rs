@@ -0,0 +1,132 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use tauri::Manager;
+use tokio::time::sleep;
+
+use crate::services::{self, ServiceKind};
+use crate::{adapters, downloads, llama_binary, llama_config, ports, settings, storage};
+
+// Tiempo máximo para que /health responda ok (modelos grandes en discos lentos)
+const READY_TIMEOUT: Duration = Duration::from_secs(300);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// Puerto por defecto de llama-server si no se pide otro
+const DEFAULT_PORT: u16 = 8080;
+
+// GGUF que sirve la instancia actual de llama-server
+static CURRENT_MODEL: Lazy<std::sync::Mutex<Option<String>>> = Lazy::new(|| std::sync::Mutex::new(None));
+
+// Modelo cargado en llama-server, si está sirviendo
+pub fn current_model() -> Option<String> {
+  ports::get(ServiceKind::Llama)?;
+  CURRENT_MODEL.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn emit_progress(app: &tauri::AppHandle, stage: &str, started: Instant, message: Option<&str>) {
+  let _ = app.emit_all("llama-server-progress", serde_json::json!({
+    "stage": stage,
+    "elapsedMs": started.elapsed().as_millis() as u64,
+    "message": message,
+  }));
+}
+
+// Espera a que /health responda 200. Mientras carga devuelve 503; si el
+// proceso termina antes se devuelve su código de salida y la cola del log.
+async fn wait_ready(app: &tauri::AppHandle, port: u16) -> Result<(), String> {
+  let client = reqwest::Client::builder().timeout(Duration::from_secs(2)).build().map_err(|e| e.to_string())?;
+  let url = format!("http://127.0.0.1:{}/health", port);
+  let started = Instant::now();
+  emit_progress(app, "starting", started, None);
+  loop {
+    if let Some(status) = services::exit_status(app, ServiceKind::Llama).await {
+      // Dar tiempo a que se vacíen los pipes antes de leer la cola
+      sleep(Duration::from_millis(200)).await;
+      let error = format!("llama-server exited ({}) while loading the model:\n{}", status, services::tail(ServiceKind::Llama).await);
+      emit_progress(app, "failed", started, Some(&error));
+      return Err(error);
+    }
+    match client.get(&url).send().await {
+      Ok(res) if res.status().is_success() => {
+        emit_progress(app, "ready", started, None);
+        return Ok(());
+      }
+      Ok(res) => {
+        let body = res.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&body).ok()
+          .and_then(|v| v.pointer("/error/message").and_then(|m| m.as_str()).map(str::to_string))
+          .unwrap_or_else(|| "Loading model".into());
+        emit_progress(app, "loading", started, Some(&message));
+      }
+      Err(_) => emit_progress(app, "starting", started, None),
+    }
+    if started.elapsed() > READY_TIMEOUT {
+      services::stop(app, ServiceKind::Llama).await;
+      let error = format!("llama-server did not become ready in {}s:\n{}", READY_TIMEOUT.as_secs(), services::tail(ServiceKind::Llama).await);
+      emit_progress(app, "failed", started, Some(&error));
+      return Err(error);
+    }
+    sleep(POLL_INTERVAL).await;
+  }
+}
+
+// Lanza llama-server y no vuelve hasta que el modelo está cargado y sirviendo.
+// Devuelve el puerto usado: el pedido si está libre o uno asignado por el sistema.
+#[tauri::command]
+pub async fn start_llama_server(app: tauri::AppHandle, model_path: String, port: Option<u16>) -> Result<u16, String> {
+  let bin = llama_binary::resolve(&app).ok_or("Binario llama-server no encontrado. Ejecuta download_llama_binary")?;
+  // Liberar el puerto de la instancia anterior antes de elegir uno
+  services::stop(&app, ServiceKind::Llama).await;
+  let port = ports::pick(port.unwrap_or(DEFAULT_PORT))?;
+
+  storage::touch_model(&app, Path::new(&model_path));
+  let config = llama_config::resolve(&settings::load(&app).llama, Path::new(&model_path))?;
+  let logs_dir = crate::app_data_dir(&app).ok_or("app_data_dir not found")?.join("logs");
+  let _ = std::fs::create_dir_all(&logs_dir);
+  let log_path = logs_dir.join("llama.log");
+  if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(&log_path) {
+    let _ = writeln!(f, "[tauri] start_llama_server {} puerto {} {}", model_path, port, config.args().join(" "));
+  }
+  crate::boot_log(&app, format!("[tauri] llama-server {}", config.args().join(" "))).await;
+
+  let mut args = vec![
+    "--model".to_string(), model_path.clone(),
+    "--port".to_string(), port.to_string(),
+    "--no-webui".to_string(),
+  ];
+  args.extend(config.args());
+  // Adaptadores LoRA instalados para este modelo base
+  let hashed = PathBuf::from(&model_path);
+  if let Ok(Ok(sha)) = tauri::async_runtime::spawn_blocking(move || downloads::model_digest(&hashed)).await {
+    for adapter in adapters::for_base(&app, &sha) {
+      crate::boot_log(&app, format!("[tauri] llama-server con adaptador '{}'", adapter.id)).await;
+      args.extend(["--lora".to_string(), adapter.path]);
+    }
+  }
+  let (envs, current_dir) = llama_binary::runtime_env(&bin);
+  let spec = services::SpawnSpec {
+    program: bin.to_string_lossy().into_owned(),
+    args,
+    envs,
+    current_dir,
+    log_path,
+    boot_log_prefix: Some("[llama]".into()),
+  };
+  // launch detiene la instancia anterior si la hay
+  services::launch(&app, ServiceKind::Llama, spec).await?;
+  wait_ready(&app, port).await?;
+  services::mark_ready(&app, ServiceKind::Llama).await;
+  ports::record(&app, ServiceKind::Llama, port, true);
+  *CURRENT_MODEL.lock().unwrap_or_else(|e| e.into_inner()) = Some(model_path.clone());
+  crate::boot_log(&app, format!("[tauri] llama-server listo en puerto {}", port)).await;
+  Ok(port)
+}
+
+#[tauri::command]
+pub async fn stop_llama_server(app: tauri::AppHandle) -> Result<(), String> {
+  services::stop(&app, ServiceKind::Llama).await;
+  *CURRENT_MODEL.lock().unwrap_or_else(|e| e.into_inner()) = None;
+  Ok(())
+}
@@ -1,17 +1,37 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use std::fs::{create_dir_all, File, OpenOptions};
-use std::io::{BufWriter, Write};
-use std::process::{Child, Command, Stdio};
-use std::net::TcpStream;
+use std::io::Write;
+#[cfg(target_os = "macos")]
+use std::process::{Command, Stdio};
 use tauri::Manager;
 use once_cell::sync::Lazy;
 use tokio::sync::RwLock;
 use tokio::time::sleep;
 use std::time::Duration;
 
-static SERVER_CHILD: tauri::async_runtime::Mutex<Option<Child>> = tauri::async_runtime::Mutex::const_new(None);
-static OLLAMA_CHILD: tauri::async_runtime::Mutex<Option<Child>> = tauri::async_runtime::Mutex::const_new(None);
+mod adapters;
+mod catalog;
+mod chat;
+mod cpu;
+mod downloads;
+mod gateway;
+mod gguf;
+mod integrity;
+mod lan;
+mod llama_binary;
+mod llama_config;
+mod llama_server;
+mod models;
+mod net;
+mod ollama;
+mod ports;
+mod profiles;
+mod scheduler;
+mod services;
+mod settings;
+mod sideload;
+mod storage;
 
 static BOOT_LOG: Lazy<RwLock<Vec<String>>> = Lazy::new(|| RwLock::new(Vec::new()));
 
@@ -31,6 +51,15 @@ async fn get_boot_log() -> Vec<String> {
   guard.clone()
 }
 
+// Directorio de datos de la app. GANADO_DATA_DIR lo reemplaza para poder correr
+// dos instancias en el mismo equipo (p. ej. al probar el intercambio por LAN)
+fn app_data_dir(app: &tauri::AppHandle) -> Option<std::path::PathBuf> {
+  match std::env::var("GANADO_DATA_DIR") {
+    Ok(dir) if !dir.trim().is_empty() => Some(std::path::PathBuf::from(dir.trim())),
+    _ => app.path_resolver().app_data_dir(),
+  }
+}
+
 fn load_env_from_file(path: &std::path::Path) -> std::collections::HashMap<String, String> {
   let mut map = std::collections::HashMap::new();
   if !path.exists() { return map; }
@@ -49,220 +78,45 @@ fn load_env_from_file(path: &std::path::Path) -> std::collections::HashMap<Strin
   map
 }
 
-#[tauri::command]
-async fn download_model(url: String, sha256_hex: Option<String>, app: tauri::AppHandle, window: tauri::Window) -> Result<String, String> {
-  let dir = app.path_resolver().app_data_dir().ok_or("app_data_dir not found")?;
-  let models_dir = dir.join("models");
-  if let Err(e) = create_dir_all(&models_dir) { return Err(format!("cannot create models dir: {}", e)); }
-  let last_seg = url.split('/').last().unwrap_or("model.gguf");
-  let no_query = last_seg.split('?').next().unwrap_or(last_seg);
-  let no_fragment = no_query.split('#').next().unwrap_or(no_query);
-  let filename = if no_fragment.is_empty() { "model.gguf" } else { no_fragment };
-  let target = models_dir.join(filename);
-
-  let client = reqwest::Client::new();
-  let res = client.get(&url).send().await.map_err(|e| e.to_string())?;
-  let total = res.content_length().unwrap_or(0);
-  let mut stream = res.bytes_stream();
-
-  let file = File::create(&target).map_err(|e| e.to_string())?;
-  let mut writer = BufWriter::new(file);
-  let mut downloaded: u64 = 0;
-
-  use futures_util::StreamExt;
-  while let Some(chunk) = stream.next().await {
-    let bytes = chunk.map_err(|e| e.to_string())?;
-    writer.write_all(&bytes).map_err(|e| e.to_string())?;
-    downloaded += bytes.len() as u64;
-    let _ = window.emit("model-download-progress", serde_json::json!({"downloaded": downloaded, "total": total }));
-  }
-  writer.flush().map_err(|e| e.to_string())?;
-
-  // Validación rápida de tamaño (evitar archivos de pocos bytes o incompletos)
-  if let Ok(meta) = std::fs::metadata(&target) {
-    if meta.len() < 100 * 1024 * 1024 { // < 100MB se considera inválido para modelos GGUF
-      return Err(format!("downloaded file too small ({} bytes)", meta.len()));
-    }
-  }
-
-  if let Some(expected) = sha256_hex {
-    use sha2::{Digest, Sha256};
-    use std::io::Read;
-    let mut f = File::open(&target).map_err(|e| e.to_string())?;
-    let mut hasher = Sha256::new();
-    let mut buf = [0u8; 8192];
-    loop {
-      let n = f.read(&mut buf).map_err(|e| e.to_string())?;
-      if n == 0 { break; }
-      hasher.update(&buf[..n]);
-    }
-    let got = hex::encode(hasher.finalize());
-    if got.to_lowercase() != expected.to_lowercase() {
-      return Err(format!("sha256 mismatch: got {}, expected {}", got, expected));
-    }
-  }
-
-  Ok(target.to_string_lossy().into_owned())
-}
-
 #[tauri::command]
 fn models_dir(app: tauri::AppHandle) -> Result<String, String> {
-  let dir = app.path_resolver().app_data_dir().ok_or("app_data_dir not found")?;
+  let dir = app_data_dir(&app).ok_or("app_data_dir not found")?;
   let md = dir.join("models");
   Ok(md.to_string_lossy().into_owned())
 }
 
 #[tauri::command]
-async fn download_llama_binary(app: tauri::AppHandle) -> Result<String, String> {
-  let dir = app.path_resolver().app_data_dir().ok_or("app_data_dir not found")?;
-  let bin_dir = dir.join("bin");
-  create_dir_all(&bin_dir).map_err(|e| e.to_string())?;
-
-  // Fallback a un binario precompilado universal para macOS si no hay var
-  let default_url = if cfg!(target_os = "macos") {
-    "https://github.com/ggerganov/llama.cpp/releases/download/b3289/llama-server-macos-universal"
-  } else if cfg!(target_os = "windows") {
-    "https://github.com/ggerganov/llama.cpp/releases/download/b3289/llama-server.exe"
-  } else {
-    "https://github.com/ggerganov/llama.cpp/releases/download/b3289/llama-server"
-  };
-  let url = std::env::var("LLAMA_BINARY_URL").unwrap_or_else(|_| default_url.to_string());
-
-  let filename = if cfg!(target_os = "windows") { "llama-server.exe" } else { "llama-server" };
-  let target = bin_dir.join(filename);
-
-  if target.exists() { return Ok(target.to_string_lossy().into_owned()); }
-
-  let client = reqwest::Client::new();
-  let res = client.get(&url).send().await.map_err(|e| e.to_string())?;
-  let bytes = res.bytes().await.map_err(|e| e.to_string())?;
-  let mut f = File::create(&target).map_err(|e| e.to_string())?;
-  f.write_all(&bytes).map_err(|e| e.to_string())?;
-
-  #[cfg(unix)]
-  {
-    use std::os::unix::fs::PermissionsExt;
-    let mut perms = std::fs::metadata(&target).map_err(|e| e.to_string())?.permissions();
-    perms.set_mode(0o755);
-    std::fs::set_permissions(&target, perms).map_err(|e| e.to_string())?;
-  }
-
-  // Validar tamaño mínimo para evitar descargas corruptas
-  if let Ok(meta) = std::fs::metadata(&target) {
-    if meta.len() < 100 * 1024 { // <100KB improbable para un binario válido
-      return Err("downloaded llama-server seems corrupted (too small)".into());
-    }
-  }
-
-  Ok(target.to_string_lossy().into_owned())
-}
-
-#[tauri::command]
-async fn start_llama_server(app: tauri::AppHandle, model_path: String, port: u16) -> Result<(), String> {
-  let dir = app.path_resolver().app_data_dir().ok_or("app_data_dir not found")?;
-  let bin = dir.join("bin").join(if cfg!(target_os = "windows") { "llama-server.exe" } else { "llama-server" });
-  if !bin.exists() { return Err("Binario llama-server no encontrado. Ejecuta download_llama_binary".into()); }
-
-  // detener si ya hay uno
-  {
-    let mut guard = SERVER_CHILD.lock().await;
-    if let Some(child) = guard.as_mut() { let _ = child.kill(); }
-    *guard = None;
-  }
-
-  let child = Command::new(bin)
-    .arg("--model").arg(model_path)
-    .arg("--port").arg(port.to_string())
-    .arg("--no-webui")
-    .stdout(Stdio::null())
-    .stderr(Stdio::null())
-    .spawn()
-    .map_err(|e| e.to_string())?;
-
-  {
-    let mut guard = SERVER_CHILD.lock().await;
-    *guard = Some(child);
-  }
-  Ok(())
-}
-
-#[tauri::command]
-async fn stop_llama_server() -> Result<(), String> {
-  let mut guard = SERVER_CHILD.lock().await;
-  if let Some(child) = guard.as_mut() {
-    let _ = child.kill();
-  }
-  *guard = None;
-  Ok(())
-}
-
-#[tauri::command]
-fn find_available_model(app: tauri::AppHandle) -> Result<String, String> {
-  // Escanear varias ubicaciones y elegir el .gguf de MAYOR tamaño
-  let mut candidates: Vec<std::path::PathBuf> = Vec::new();
-  if let Some(dir) = app.path_resolver().app_data_dir() {
-    candidates.push(dir.join("models"));
-  }
-  if let Some(rd) = app.path_resolver().resource_dir() {
-    candidates.push(rd.join("models"));
-  }
-  if cfg!(debug_assertions) {
-    if let Ok(cwd) = std::env::current_dir() {
-      candidates.push(cwd.join("src-tauri").join("models"));
-    }
-  }
-
-  let mut best: Option<(u64, std::path::PathBuf)> = None;
-  for dir in candidates {
-    if let Ok(rd) = std::fs::read_dir(&dir) {
-      for entry in rd.flatten() {
-        let p = entry.path();
-        if p.extension().and_then(|s| s.to_str()).unwrap_or("") == "gguf" {
-          if let Ok(meta) = std::fs::metadata(&p) {
-            let sz = meta.len();
-            if best.as_ref().map(|(b, _)| sz > *b).unwrap_or(true) {
-              best = Some((sz, p));
-            }
-          }
-        }
-      }
-    }
-  }
-
-  if let Some((_, path)) = best { return Ok(path.to_string_lossy().into_owned()); }
-  Err("no local model found".into())
-}
-
-#[tauri::command]
-async fn start_ollama_server(app: tauri::AppHandle, port: u16) -> Result<(), String> {
+async fn start_ollama_server(app: tauri::AppHandle, port: Option<u16>) -> Result<u16, String> {
+  // Puerto pedido, el de la instancia ya en marcha o el configurado
+  let preferred = port
+    .or_else(|| ports::get(services::ServiceKind::Ollama).map(|e| e.port))
+    .or_else(|| std::env::var("NEXT_PUBLIC_LLAMA_PORT").ok().and_then(|s| s.parse::<u16>().ok()))
+    .unwrap_or(11434);
   // Preparar archivo de log persistente
-  let data_dir = app.path_resolver().app_data_dir().ok_or("app_data_dir not found")?;
+  let data_dir = app_data_dir(&app).ok_or("app_data_dir not found")?;
   let logs_dir = data_dir.join("logs");
   let _ = std::fs::create_dir_all(&logs_dir);
   let log_path = logs_dir.join("ollama.log");
   let mut logf = OpenOptions::new().create(true).append(true).open(&log_path).map_err(|e| e.to_string())?;
-  let _ = writeln!(logf, "[tauri] start_ollama_server: inicio puerto {}", port);
+  let _ = writeln!(logf, "[tauri] start_ollama_server: inicio puerto {}", preferred);
   // Enviar evento para UI de splash
-  boot_log(&app, format!("[tauri] start_ollama_server puerto {}", port)).await;
-  // Chequeo previo: si ya responde /api/tags en este puerto, no hacer nada
-  let pre_client = reqwest::Client::builder()
-    .timeout(Duration::from_millis(800))
-    .build()
-    .map_err(|e| e.to_string())?;
-  let pre_url = format!("http://127.0.0.1:{}/api/tags", port);
-  if let Ok(resp) = pre_client.get(&pre_url).send().await {
-    if resp.status().is_success() {
-      let _ = writeln!(logf, "[tauri] start_ollama_server: ya estaba listo en {}", pre_url);
-      boot_log(&app, format!("[tauri] ollama ya listo en {}", pre_url)).await;
-      return Ok(());
-    }
+  boot_log(&app, format!("[tauri] start_ollama_server puerto {}", preferred)).await;
+  // Chequeo previo: reutilizar el Ollama solo si lo lanzó esta app; otro en el
+  // mismo puerto (p. ej. el del sistema) obliga a buscar un puerto libre
+  if ports::is_own_ollama(preferred).await {
+    ports::record(&app, services::ServiceKind::Ollama, preferred, true);
+    let _ = writeln!(logf, "[tauri] start_ollama_server: ya estaba listo en puerto {}", preferred);
+    boot_log(&app, format!("[tauri] ollama ya listo en puerto {}", preferred)).await;
+    return Ok(preferred);
   }
-  // detener si ya hay uno
-  {
-    let mut guard = OLLAMA_CHILD.lock().await;
-    if let Some(child) = guard.as_mut() { let _ = child.kill(); }
-    *guard = None;
+  // Si otro programa ocupa el puerto, pedir uno libre
+  let port = ports::pick(preferred)?;
+  if port != preferred {
+    let _ = writeln!(logf, "[tauri] puerto {} ocupado por otro programa; ollama usará {}", preferred, port);
+    boot_log(&app, format!("[tauri] puerto {} ocupado por otro programa; ollama usará {}", preferred, port)).await;
   }
+  // detener si ya hay uno
+  services::stop(&app, services::ServiceKind::Ollama).await;
 
   // Resolver binario de Ollama: OLLAMA_BIN, PATH, rutas comunes y binario empaquetado en Resources
   let candidate_env = std::env::var("OLLAMA_BIN").ok();
@@ -272,38 +126,48 @@ async fn start_ollama_server(app: tauri::AppHandle, port: u16) -> Result<(), Str
     "/usr/local/bin/ollama".to_string(),
     "/usr/bin/ollama".to_string(),
   ];
-  // Añadir binario empaquetado: si existe comprimido (bin/ollama.gz), descomprimir a app_data/bin y usarlo
-  if let Some(res_gz) = app.path_resolver().resolve_resource("bin/ollama.gz") {
-    if res_gz.exists() {
-      let app_bin_dir = data_dir.join("bin");
-      let _ = std::fs::create_dir_all(&app_bin_dir);
-      let target = app_bin_dir.join("ollama");
-      if !target.exists() {
-        if let Ok(bytes) = std::fs::read(&res_gz) {
-          // Descomprimir gzip en memoria
-          use std::io::Read;
-          let mut gz = flate2::read::GzDecoder::new(&bytes[..]);
-          let mut out: Vec<u8> = Vec::new();
-          if gz.read_to_end(&mut out).is_ok() {
-            if std::fs::write(&target, &out).is_ok() {
-              #[cfg(unix)]
-              {
-                use std::os::unix::fs::PermissionsExt;
-                if let Ok(mut perms) = std::fs::metadata(&target).map(|m| m.permissions()) {
-                  perms.set_mode(0o755);
-                  let _ = std::fs::set_permissions(&target, perms);
+  // Binarios empaquetados por nivel de CPU (bin/ollama-avx2.gz, bin/ollama-avx.exe, ...)
+  // de la variante más optimizada compatible a la genérica (bin/ollama.gz)
+  let cpu_features = cpu::features();
+  for level in cpu::compatible_levels(&cpu_features) {
+    let name = if level == "generic" { "ollama".to_string() } else { format!("ollama-{}", level) };
+    // Si existe comprimido, descomprimir a app_data/bin y usarlo
+    if let Some(res_gz) = app.path_resolver().resolve_resource(format!("bin/{}.gz", name)) {
+      if res_gz.exists() {
+        let app_bin_dir = data_dir.join("bin");
+        let _ = std::fs::create_dir_all(&app_bin_dir);
+        let target = app_bin_dir.join(&name);
+        if !target.exists() {
+          if let Ok(bytes) = std::fs::read(&res_gz) {
+            // Descomprimir gzip en memoria
+            use std::io::Read;
+            let mut gz = flate2::read::GzDecoder::new(&bytes[..]);
+            let mut out: Vec<u8> = Vec::new();
+            if gz.read_to_end(&mut out).is_ok() {
+              if std::fs::write(&target, &out).is_ok() {
+                #[cfg(unix)]
+                {
+                  use std::os::unix::fs::PermissionsExt;
+                  if let Ok(mut perms) = std::fs::metadata(&target).map(|m| m.permissions()) {
+                    perms.set_mode(0o755);
+                    let _ = std::fs::set_permissions(&target, perms);
+                  }
                 }
               }
             }
           }
         }
+        if target.exists() { candidates.push(target.to_string_lossy().into_owned()); }
       }
-      if target.exists() { candidates.push(target.to_string_lossy().into_owned()); }
+    }
+    if let Some(res_bin) = app.path_resolver().resolve_resource(format!("bin/{}.exe", name)) {
+      if res_bin.exists() { candidates.push(res_bin.to_string_lossy().into_owned()); }
     }
   }
-  if let Some(res_bin) = app.path_resolver().resolve_resource("bin/ollama.exe") {
-    if res_bin.exists() { candidates.push(res_bin.to_string_lossy().into_owned()); }
-  }
+  // Sin AVX2 se fuerza un runner de CPU compatible salvo que el usuario ya lo fije
+  let llm_library = std::env::var("OLLAMA_LLM_LIBRARY").ok()
+    .or_else(|| cpu::ollama_llm_library(&cpu_features).map(str::to_string));
+  boot_log(&app, format!("[tauri] CPU {}: runner de Ollama {}", cpu::best_level(&cpu_features), llm_library.as_deref().unwrap_or("automático"))).await;
   // Agregar candidatos dentro de la app Ollama (macOS)
   #[cfg(target_os = "macos")]
   {
@@ -328,27 +192,28 @@ async fn start_ollama_server(app: tauri::AppHandle, port: u16) -> Result<(), Str
   let _ = std::fs::create_dir_all(&app_models_root);
   for bin in candidates.clone() {
     let _ = writeln!(logf, "[tauri] intentando lanzar '{} serve'", bin);
-    let mut cmd = Command::new(&bin);
-    // Redirigir stdout/err al log para diagnósticos
-    let out = OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap());
-    let err = OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap());
     // Importante: OLLAMA_ORIGINS debe ir separado por comas, no por espacios,
     // para que Gin CORS no lo interprete como un único patrón y falle con
     // "only one * is allowed".
     let allowed_origins = "app://*,file://*,tauri://*,http://localhost,https://localhost,http://127.0.0.1,https://127.0.0.1";
-    cmd.env("OLLAMA_HOST", format!("127.0.0.1:{}", port))
-      .env("OLLAMA_MODELS", &app_models_root)
-      .env("OLLAMA_ORIGINS", allowed_origins)
-      .arg("serve")
-      .stdout(Stdio::from(out))
-      .stderr(Stdio::from(err));
-
-    match cmd.spawn() {
-      Ok(child) => {
-        {
-          let mut guard = OLLAMA_CHILD.lock().await;
-          *guard = Some(child);
-        }
+    let mut envs = vec![
+      ("OLLAMA_HOST".to_string(), format!("127.0.0.1:{}", port)),
+      ("OLLAMA_MODELS".to_string(), app_models_root.to_string_lossy().into_owned()),
+      ("OLLAMA_ORIGINS".to_string(), allowed_origins.to_string()),
+    ];
+    if let Some(lib) = llm_library.as_deref() { envs.push(("OLLAMA_LLM_LIBRARY".to_string(), lib.to_string())); }
+    // stdout/err van al log para diagnósticos
+    let spec = services::SpawnSpec {
+      program: bin.clone(),
+      args: vec!["serve".to_string()],
+      envs,
+      current_dir: None,
+      log_path: log_path.clone(),
+      boot_log_prefix: None,
+    };
+
+    match services::launch(&app, services::ServiceKind::Ollama, spec).await {
+      Ok(_) => {
         // Esperar readiness del endpoint /api/tags
         let mut ready = false;
         for _ in 0..120 { // ~24s
@@ -358,20 +223,20 @@ async fn start_ollama_server(app: tauri::AppHandle, port: u16) -> Result<(), Str
           sleep(Duration::from_millis(200)).await;
         }
         if ready {
+          services::mark_ready(&app, services::ServiceKind::Ollama).await;
+          ports::record(&app, services::ServiceKind::Ollama, port, true);
           let _ = writeln!(logf, "[tauri] ollama listo en {}", url);
           boot_log(&app, format!("[tauri] ollama listo en {}", url)).await;
-          return Ok(());
+          return Ok(port);
         }
         last_err = Some("ollama serve did not become ready".into());
         // Si no estuvo listo, matar e intentar siguiente candidato
-        let mut guard = OLLAMA_CHILD.lock().await;
-        if let Some(child) = guard.as_mut() { let _ = child.kill(); }
-        *guard = None;
+        services::stop(&app, services::ServiceKind::Ollama).await;
       },
       Err(e) => {
         let _ = writeln!(logf, "[tauri] fallo al ejecutar '{}': {}", bin, e);
         boot_log(&app, format!("[tauri] fallo al ejecutar '{}': {}", bin, e)).await;
-        last_err = Some(format!("{}", e));
+        last_err = Some(e);
       }
     }
   }
@@ -387,11 +252,14 @@ async fn start_ollama_server(app: tauri::AppHandle, port: u16) -> Result<(), Str
       .stdout(Stdio::null())
       .stderr(Stdio::null())
       .status();
-    // Esperar hasta 24s
+    // Esperar hasta 24s; Ollama.app siempre escucha en su puerto por defecto
     let mut attempts = 0u32;
     while attempts < 120 {
-      if let Ok(resp) = client.get(&url).send().await {
-        if resp.status().is_success() { let _ = writeln!(logf, "[tauri] Ollama.app lista"); boot_log(&app, "[tauri] Ollama.app lista".to_string()).await; return Ok(()); }
+      if ports::is_ollama(11434).await {
+        ports::record(&app, services::ServiceKind::Ollama, 11434, false);
+        let _ = writeln!(logf, "[tauri] Ollama.app lista");
+        boot_log(&app, "[tauri] Ollama.app lista".to_string()).await;
+        return Ok(11434);
       }
       sleep(Duration::from_millis(200)).await;
       attempts += 1;
@@ -404,174 +272,144 @@ async fn start_ollama_server(app: tauri::AppHandle, port: u16) -> Result<(), Str
 }
 
 #[tauri::command]
-async fn stop_ollama_server() -> Result<(), String> {
-  let mut guard = OLLAMA_CHILD.lock().await;
-  if let Some(child) = guard.as_mut() { let _ = child.kill(); }
-  *guard = None;
+async fn stop_ollama_server(app: tauri::AppHandle) -> Result<(), String> {
+  services::stop(&app, services::ServiceKind::Ollama).await;
   Ok(())
 }
 
 #[tauri::command]
 async fn ensure_ollama_model_available(app: tauri::AppHandle, tag: String, model_path: Option<String>) -> Result<(), String> {
   // Usar el almacén de modelos propio de la app para evitar blobs corruptos en ~/.ollama
-  let data_dir = app.path_resolver().app_data_dir().ok_or("app_data_dir not found")?;
+  let data_dir = app_data_dir(&app).ok_or("app_data_dir not found")?;
   let app_models_root = data_dir.join("ollama-store");
   let _ = std::fs::create_dir_all(&app_models_root);
-  // Verifica si el tag ya existe consultando el endpoint local
-  let port = std::env::var("NEXT_PUBLIC_LLAMA_PORT").ok().and_then(|s| s.parse::<u16>().ok()).unwrap_or(11434);
-  let client = reqwest::Client::builder().timeout(Duration::from_millis(1500)).build().map_err(|e| e.to_string())?;
-  let url = format!("http://127.0.0.1:{}/api/tags", port);
-  if let Ok(resp) = client.get(&url).send().await {
-    if let Ok(text) = resp.text().await {
-      if text.contains(&format!("\"{}\"", tag)) {
-        // Validación rápida: intento mínimo de chat para detectar modelo corrupto
-        let chat_url = format!("http://127.0.0.1:{}/api/chat", port);
-        let body = serde_json::json!({
-          "model": tag,
-          "messages": [{"role": "user", "content": "hola"}],
-          "stream": false
-        });
-        if let Ok(r) = client.post(&chat_url).json(&body).send().await {
-          if !r.status().is_success() {
-            // Respuesta 5xx indica posible modelo corrupto
-            boot_log(&app, format!("[tauri] modelo '{}' parece corrupto. Eliminando y re-creando…", tag)).await;
-            let _ = Command::new("ollama")
-              .arg("rm").arg(&tag)
-              .env("OLLAMA_HOST", format!("127.0.0.1:{}", port))
-              .stdout(Stdio::null()).stderr(Stdio::null()).status();
-          } else {
-            boot_log(&app, format!("[tauri] modelo '{}' ya disponible", tag)).await;
-            return Ok(());
-          }
-        }
+  // Verifica si el tag ya existe en el Ollama que arrancó start_ollama_server
+  let ollama = ollama::OllamaClient::from_state()?;
+  // Perfil (system, PARAMETER, TEMPLATE, ADAPTER) con el que debe existir el tag
+  let base_profile = profiles::for_tag(&app, &tag)?;
+  let applied = profiles::applied(&app, &tag);
+  let requested_sha = model_path.as_deref().and_then(|p| downloads::read_digest_sidecar(std::path::Path::new(p)));
+  // Los adaptadores LoRA instalados para el modelo base entran como ADAPTER
+  let mut profile = base_profile.clone();
+  if let Some(sha) = requested_sha.as_ref().or(applied.as_ref().map(|a| &a.gguf_sha256)) {
+    profile.adapters.extend(adapters::for_base(&app, sha).into_iter().map(|a| a.path));
+  }
+  let stale = match &applied {
+    None => Some("sin perfil aplicado".to_string()),
+    Some(a) if a.fingerprint != profile.fingerprint() => Some(format!("perfil '{}' cambió (v{} → v{})", profile.name, a.version, profile.version)),
+    Some(a) if requested_sha.as_ref().is_some_and(|sha| *sha != a.gguf_sha256) => Some("el GGUF cambió".to_string()),
+    _ => None,
+  };
+  // Un Ollama caído o incompatible no es "falta el modelo": recrearlo no arreglaría nada
+  let exists = match ollama.has_model(&tag).await {
+    Ok(exists) => exists,
+    Err(e) => {
+      boot_log(&app, format!("[tauri] no se pudo consultar '{}' en ollama: {}", tag, e)).await;
+      return Err(e.into());
+    }
+  };
+  if let (true, Some(reason)) = (exists, &stale) {
+    boot_log(&app, format!("[tauri] recreando '{}': {}", tag, reason)).await;
+  } else if exists {
+    // Verificación sin cargar el modelo: manifiesto y blobs contra el sha256 del GGUF
+    let report = integrity::verify(&app, &tag, applied.as_ref().map(|a| a.gguf_sha256.clone())).await?;
+    match report.status {
+      integrity::IntegrityStatus::Ok => {
+        boot_log(&app, format!("[tauri] modelo '{}' ya disponible (digest verificado)", tag)).await;
+        return Ok(());
+      }
+      integrity::IntegrityStatus::Unverifiable => {
+        boot_log(&app, format!("[tauri] modelo '{}' ya disponible (almacén externo, sin verificar)", tag)).await;
+        return Ok(());
+      }
+      _ => {
+        boot_log(&app, format!("[tauri] modelo '{}' dañado ({}). Re-creando…", tag, report.problems.join("; "))).await;
+        integrity::discard_corrupt(&report);
       }
     }
   }
 
-  // Determinar ruta del GGUF
-  let gguf = if let Some(p) = model_path { p } else { find_available_model(app.clone())? };
-
-  // Crear Modelfile temporal
-  let dir = app.path_resolver().app_data_dir().ok_or("app_data_dir not found")?;
-  let tmp = dir.join("Modelfile");
-  {
-    let mut f = File::create(&tmp).map_err(|e| e.to_string())?;
-    let line = format!("FROM \"{}\"\n", gguf.replace('"', "\\\""));
-    f.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
-  }
-
-  // Ejecutar `ollama create <tag> -f <Modelfile>` buscando binarios en rutas comunes
-  let candidate_env = std::env::var("OLLAMA_BIN").ok();
-  let mut candidates = vec![
-    candidate_env.clone().unwrap_or_else(|| "ollama".to_string()),
-    "/opt/homebrew/bin/ollama".to_string(),
-    "/usr/local/bin/ollama".to_string(),
-    "/usr/bin/ollama".to_string(),
-  ];
-  // Añadir binario empaquetado en Resources/bin/ollama si existe
-  if let Some(res_bin) = app.path_resolver().resolve_resource("bin/ollama") {
-    if res_bin.exists() { candidates.push(res_bin.to_string_lossy().into_owned()); }
-  }
-  if let Some(res_bin) = app.path_resolver().resolve_resource("bin/ollama.exe") {
-    if res_bin.exists() { candidates.push(res_bin.to_string_lossy().into_owned()); }
-  }
-  #[cfg(target_os = "macos")]
-  {
-    let home = std::env::var("HOME").unwrap_or_default();
-    candidates.push("/Applications/Ollama.app/Contents/MacOS/ollama".to_string());
-    if !home.is_empty() {
-      candidates.push(format!("{}/Applications/Ollama.app/Contents/MacOS/ollama", home));
+  // Determinar ruta del GGUF: si el tag ya existió se recrea con el mismo GGUF
+  // (por su sha256), no con el que elegiría select_best_model ahora
+  let gguf = match (model_path, &applied) {
+    (Some(p), _) => p,
+    (None, Some(a)) => models::find_by_digest(&app, &a.gguf_sha256, Some(&a.gguf_path))
+      .ok_or_else(|| format!("el GGUF de '{}' (sha256 {}) ya no está instalado", tag, a.gguf_sha256))?,
+    (None, None) => {
+      let selection = models::select_best_model(&app)?;
+      boot_log(&app, format!("[tauri] modelo elegido {}: {}", selection.path, selection.reason)).await;
+      selection.path
     }
-  }
-
-  let mut created = false;
-  let models_env = app_models_root.to_string_lossy().into_owned();
-  for bin in candidates {
-    // Intento defensivo: borrar tag previo si existe para evitar referenciar blobs corruptos
-    boot_log(&app, format!("[tauri] creando tag '{}' con binario {}", tag, bin)).await;
-    let _ = Command::new(&bin)
-      .arg("rm")
-      .arg(&tag)
-      .env("OLLAMA_HOST", format!("127.0.0.1:{}", port))
-      .env("OLLAMA_MODELS", &models_env)
-      .stdout(Stdio::null())
-      .stderr(Stdio::null())
-      .status();
+  };
 
-    let status = Command::new(&bin)
-      .arg("create")
-      .arg(&tag)
-      .arg("-f").arg(&tmp)
-      .env("OLLAMA_HOST", format!("127.0.0.1:{}", port))
-      .env("OLLAMA_MODELS", &models_env)
-      .stdout(Stdio::null())
-      .stderr(Stdio::null())
-      .status();
-    match status {
-      Ok(s) if s.success() => { created = true; boot_log(&app, format!("[tauri] tag '{}' creado", tag)).await; break; }
-      _ => {}
-    }
+  // Importar el GGUF copia el archivo como blob: debe caber en ollama-store
+  let gguf_path = std::path::Path::new(&gguf);
+  let already_imported = downloads::read_digest_sidecar(gguf_path)
+    .map(|sha| app_models_root.join("blobs").join(format!("sha256-{}", sha)).exists())
+    .unwrap_or(false);
+  if !already_imported {
+    storage::preflight(&app, &app_models_root, std::fs::metadata(gguf_path).map(|m| m.len()).unwrap_or(0))?;
   }
-
-  // Fallback macOS: abrir la app Ollama y reintentar una vez que el server esté listo
-  #[cfg(target_os = "macos")]
-  if !created {
-    let _ = Command::new("open").arg("-a").arg("Ollama").stdout(Stdio::null()).stderr(Stdio::null()).status();
-    // Esperar hasta 10s a que el server esté arriba
-    let mut attempts = 0u32;
-    while attempts < 50 {
-      if let Ok(resp) = client.get(&url).send().await { if resp.status().is_success() { break; } }
-      sleep(Duration::from_millis(200)).await;
-      attempts += 1;
+  storage::touch_model(&app, gguf_path);
+  let hashed = gguf_path.to_path_buf();
+  let gguf_sha = tauri::async_runtime::spawn_blocking(move || downloads::model_digest(&hashed)).await.map_err(|e| e.to_string())??;
+  let mut profile = base_profile;
+  profile.adapters.extend(adapters::for_base(&app, &gguf_sha).into_iter().map(|a| a.path));
+
+  // Subir el blob (si falta) y crear el tag vía API; el progreso llega a la UI como ollama-model-progress
+  boot_log(&app, format!("[tauri] creando tag '{}' desde {}", tag, gguf)).await;
+  let sink = ollama::ProgressSink::new(&app, &tag);
+  let digest = match ollama.import_gguf(profile.create_request(&tag), gguf_path, &profile.adapter_paths(), &sink).await {
+    Ok(digest) => digest,
+    Err(e) => {
+      boot_log(&app, format!("[tauri] no se pudo crear '{}': {}", tag, e)).await;
+      return Err(e.into());
     }
-    // Reintento con "ollama" en PATH
-    // Borrar tag y recrear apuntando al GGUF desde nuestro almacén
-    let _ = Command::new("ollama")
-      .arg("rm").arg(&tag)
-      .env("OLLAMA_HOST", format!("127.0.0.1:{}", port))
-      .env("OLLAMA_MODELS", &models_env)
-      .stdout(Stdio::null()).stderr(Stdio::null()).status();
-    if let Ok(s) = Command::new("ollama")
-      .arg("create").arg(&tag).arg("-f").arg(&tmp)
-      .env("OLLAMA_HOST", format!("127.0.0.1:{}", port))
-      .env("OLLAMA_MODELS", &models_env)
-      .stdout(Stdio::null()).stderr(Stdio::null()).status() {
-      if s.success() { created = true; }
-    }
-  }
+  };
+  boot_log(&app, format!("[tauri] tag '{}' creado con perfil '{}' v{}", tag, profile.name, profile.version)).await;
 
-  if !created { return Err("ollama create failed".into()); }
-  // Validar carga real del modelo con un chat mínimo
-  let chat_url = format!("http://127.0.0.1:{}/api/chat", port);
-  let body = serde_json::json!({
-    "model": tag,
-    "messages": [{"role": "user", "content": "hola"}],
-    "stream": false
-  });
-  match client.post(&chat_url).json(&body).send().await {
-    Ok(r) if r.status().is_success() => Ok(()),
-    _ => {
-      // Borrar tag inválido y reportar error claro
-      let _ = Command::new("ollama")
-        .arg("rm").arg(&tag)
-        .env("OLLAMA_HOST", format!("127.0.0.1:{}", port))
-        .env("OLLAMA_MODELS", &models_env)
-        .stdout(Stdio::null()).stderr(Stdio::null()).status();
-      boot_log(&app, format!("[tauri] validación de chat falló para '{}': modelo dañado", tag)).await;
-      Err("Modelo local dañado o incompleto. Conéctate para re-descargar el modelo.".into())
+  // Comprobar que el tag quedó apuntando a blobs íntegros del GGUF importado
+  let report = integrity::verify(&app, &tag, Some(digest.clone())).await?;
+  if report.usable() {
+    if let Err(e) = profiles::record_applied(&app, &tag, &profile, gguf_path, &digest) {
+      boot_log(&app, format!("[tauri] no se registró el perfil de '{}': {}", tag, e)).await;
     }
+    Ok(())
+  } else {
+    // Borrar tag inválido y reportar error claro
+    let _ = ollama.delete(&tag).await;
+    integrity::discard_corrupt(&report);
+    boot_log(&app, format!("[tauri] verificación falló para '{}': {}", tag, report.problems.join("; "))).await;
+    Err("Modelo local dañado o incompleto. Conéctate para re-descargar el modelo.".into())
   }
 }
 
 fn main() {
   tauri::Builder::default()
-    .invoke_handler(tauri::generate_handler![download_model, models_dir, download_llama_binary, start_llama_server, stop_llama_server, find_available_model, start_ollama_server, stop_ollama_server, ensure_ollama_model_available, get_boot_log])
+    .invoke_handler(tauri::generate_handler![downloads::download_model, downloads::pause_download, downloads::resume_download, downloads::cancel_download, catalog::list_catalog_models, catalog::install_model, catalog::uninstall_model, gguf::inspect_model, sideload::import_model_from_path, sideload::export_model_to_path, lan::set_lan_sharing, lan::lan_sharing_status, lan::list_lan_peers, storage::storage_report, storage::set_storage_quota, storage::list_eviction_candidates, storage::evict_models, models_dir, llama_binary::download_llama_binary, llama_binary::upgrade_llama_binary, llama_binary::rollback_llama_binary, llama_binary::llama_binary_status, llama_config::get_llama_config, llama_config::set_llama_config, llama_server::start_llama_server, llama_server::stop_llama_server, models::find_available_model, models::select_model, models::pin_model, models::list_local_models, models::loaded_models, models::unload_model, models::get_default_model, models::set_default_model, start_ollama_server, stop_ollama_server, ensure_ollama_model_available, get_boot_log, cpu::system_capabilities, services::get_services_status, ports::get_service_endpoints, ollama::pull_ollama_model, ollama::delete_ollama_model, ollama::show_ollama_model, profiles::list_model_profiles, profiles::get_model_profile, profiles::save_model_profile, adapters::install_adapter, adapters::list_adapters, adapters::remove_adapter, integrity::verify_ollama_model, integrity::smoke_test_ollama_model, chat::ai_chat_stream, chat::ai_cancel, scheduler::inference_queue_status, scheduler::get_scheduler_config, scheduler::set_scheduler_config])
     .setup(|app| {
+      // Cerrar procesos huérfanos de una ejecución anterior antes de lanzar los
+      // nuevos (la espera hasta que salen corre en segundo plano)
+      {
+        let handle = app.app_handle();
+        services::reap_orphans(&handle);
+        ports::reset(&handle);
+      }
+      // Vigilar los procesos hijos y relanzarlos si se caen
+      services::spawn_supervisor(app.app_handle());
+      // Endpoint OpenAI único delante de Ollama y llama-server
+      {
+        let handle = app.app_handle();
+        tauri::async_runtime::spawn(async move {
+          if let Err(e) = gateway::start(&handle).await {
+            boot_log(&handle, format!("[tauri] gateway no disponible: {}", e)).await;
+          }
+        });
+      }
       // Autoinicio del servidor de Ollama y preparación del modelo en segundo plano (dev y prod)
       {
         let handle = app.app_handle();
         tauri::async_runtime::spawn(async move {
-          let port = std::env::var("NEXT_PUBLIC_LLAMA_PORT").ok().and_then(|s| s.parse::<u16>().ok()).unwrap_or(11434);
           // Copiar modelo desde Resources/models a app_data/models si no existe aún
           if let Some(res_dir) = handle.path_resolver().resource_dir() {
             let bundled = res_dir.join("models");
@@ -579,12 +417,14 @@ fn main() {
               while let Some(Ok(entry)) = rd.next() {
                 let p = entry.path();
                 if p.extension().and_then(|s| s.to_str()).unwrap_or("") == "gguf" {
-                  if let Some(app_data) = handle.path_resolver().app_data_dir() {
-                    let dst_dir = app_data.join("models");
-                    let _ = std::fs::create_dir_all(&dst_dir);
-                    let dst = dst_dir.join(p.file_name().unwrap_or_default());
+                  if let Some(app_data) = app_data_dir(&handle) {
+                    let dst = app_data.join("models").join(p.file_name().unwrap_or_default());
                     if !dst.exists() {
-                      let _ = std::fs::copy(&p, &dst);
+                      let (h, src) = (handle.clone(), p.clone());
+                      let installed = tauri::async_runtime::spawn_blocking(move || sideload::install_bundled(&h, &src)).await.map_err(|e| e.to_string()).and_then(|r| r);
+                      if let Err(e) = installed {
+                        boot_log(&handle, format!("[tauri] no se copia {}: {}", p.display(), e)).await;
+                      }
                     }
                   }
                 }
@@ -592,11 +432,22 @@ fn main() {
             }
           }
           // Arrancar servidor
-          let _ = start_ollama_server(handle.clone(), port).await;
-          // Intentar asegurar modelo DeepSeek (tag por defecto)
-          let _ = ensure_ollama_model_available(handle.clone(), "deepseek-r1-qwen-1_5b:latest".to_string(), None).await;
+          let _ = start_ollama_server(handle.clone(), None).await;
+          // Asegurar el modelo por defecto (elegido con set_default_model o DeepSeek)
+          let _ = ensure_ollama_model_available(handle.clone(), models::default_model(&handle), None).await;
         });
       }
+      // Servicio LAN de modelos solo si el usuario lo activó
+      {
+        let handle = app.app_handle();
+        if settings::load(&handle).lan_sharing {
+          tauri::async_runtime::spawn(async move {
+            if let Err(e) = lan::start(&handle).await {
+              boot_log(&handle, format!("[tauri] no se pudo iniciar el servicio LAN: {}", e)).await;
+            }
+          });
+        }
+      }
       // En desarrollo: no arrancar Next standalone; Tauri ya carga devPath
       if cfg!(debug_assertions) {
         return Ok(());
@@ -604,7 +455,7 @@ fn main() {
 
       // Producción: preferir remoto si hay Internet; si no, lanzar servidor Next standalone
       let app_dir = app.path_resolver().resource_dir().ok_or("resource_dir not found")?;
-      let data_dir = app.path_resolver().app_data_dir().ok_or("app_data_dir not found")?;
+      let data_dir = app_data_dir(&app.app_handle()).ok_or("app_data_dir not found")?;
       let logs_dir = data_dir.join("logs");
       let _ = create_dir_all(&logs_dir);
       let log_path = logs_dir.join("standalone.log");
@@ -619,7 +470,8 @@ fn main() {
       ];
       let server_js = candidate_paths.iter().find(|p| p.exists()).cloned();
 
-      let port = std::env::var("NEXT_PORT").ok().and_then(|s| s.parse::<u16>().ok()).unwrap_or(4317);
+      let mut port = std::env::var("NEXT_PORT").ok().and_then(|s| s.parse::<u16>().ok()).unwrap_or(4317);
+      let instance_token = ports::instance_token(&app.app_handle());
       // Ruta al .env en Resources y mapa cargado (para Prisma: DATABASE_URL)
       let env_file = app_dir.join(".env");
       let env_map = load_env_from_file(&env_file);
@@ -650,12 +502,21 @@ fn main() {
       }
 
       if let Some(srv) = server_js {
-        // Si el puerto ya está ocupado (posible instancia previa), considerarlo disponible
-        let prebound = TcpStream::connect(("127.0.0.1", port)).is_ok();
+        // Si el puerto ya está ocupado, reutilizarlo solo si responde el handshake
+        // (otra instancia de la app); si es otro programa, buscar un puerto libre
+        let occupied = !ports::is_free(port);
+        let prebound = occupied && tauri::async_runtime::block_on(ports::verify_node(port, &instance_token));
         if prebound {
           started = true;
+          ports::record(&app.app_handle(), services::ServiceKind::Node, port, false);
         } else {
         attempted_start = true;
+        if occupied {
+          let busy = port;
+          port = ports::free_port()?;
+          let mut f = OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap());
+          let _ = writeln!(f, "[GanadoAI] Puerto {} ocupado por otro programa; usando {}", busy, port);
+        }
         // Preferir sidecar node si existe
         // Candidatos sidecar en Resources; filtrar por existencia real
         let sidecar_candidates = [
@@ -685,74 +546,42 @@ fn main() {
         // Permitir modo no autenticado SOLO en desarrollo o si ALLOW_DEV_UNAUTH=1 ya viene seteado
         let allow_dev_unauth = std::env::var("ALLOW_DEV_UNAUTH").ok().as_deref() == Some("1") || cfg!(debug_assertions);
 
-        if let Some(node_bin) = prefer_node {
-          let mut cmd = Command::new(node_bin);
-          let sidecar_attempt = cmd
-            .arg(&srv)
-            .env("PORT", port.to_string())
-            .env("HOST", "127.0.0.1")
-            .envs(if allow_dev_unauth { vec![("ALLOW_DEV_UNAUTH", "1")] } else { vec![] as Vec<(&str,&str)> })
-            .envs(env_map.iter().map(|(k,v)| (k.as_str(), v.as_str())))
-            .current_dir(srv.parent().unwrap_or(&app_dir))
-            .stdout(Stdio::from(OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap())))
-            .stderr(Stdio::from(OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap())))
-            .spawn();
-
-          match sidecar_attempt {
-            Ok(child) => {
-              tauri::async_runtime::block_on(async {
-                let mut guard = SERVER_CHILD.lock().await;
-                *guard = Some(child);
-              });
-              // No marcar started aún; esperaremos readiness del puerto
-            }
+        // Sidecar preferido y luego 'node' del sistema; el supervisor reutiliza el mismo spec al reiniciar
+        let mut node_candidates: Vec<String> = prefer_node.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+        node_candidates.push("node".to_string());
+        let mut envs = vec![
+          ("PORT".to_string(), port.to_string()),
+          ("HOST".to_string(), "127.0.0.1".to_string()),
+          ("GANADO_INSTANCE_TOKEN".to_string(), instance_token.clone()),
+        ];
+        if let Some(file) = ports::endpoints_file(&app.app_handle()) {
+          envs.push(("GANADO_ENDPOINTS_FILE".to_string(), file.to_string_lossy().into_owned()));
+        }
+        if allow_dev_unauth { envs.push(("ALLOW_DEV_UNAUTH".to_string(), "1".to_string())); }
+        envs.extend(env_map.iter().map(|(k, v)| (k.clone(), v.clone())));
+        let mut launched = false;
+        for node_bin in node_candidates {
+          let spec = services::SpawnSpec {
+            program: node_bin.clone(),
+            args: vec![srv.to_string_lossy().into_owned()],
+            envs: envs.clone(),
+            current_dir: Some(srv.parent().unwrap_or(&app_dir).to_path_buf()),
+            log_path: log_path.clone(),
+            boot_log_prefix: None,
+          };
+          match tauri::async_runtime::block_on(services::launch(&app.app_handle(), services::ServiceKind::Node, spec)) {
+            // No marcar started aún; esperaremos readiness del puerto
+            Ok(_) => { launched = true; break; }
             Err(e) => {
               // Registrar error de spawn en log
               let mut f = OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap());
-              let _ = writeln!(f, "[GanadoAI] Error al iniciar sidecar/node: {}", e);
-              // Segundo intento: usar 'node' del sistema
-              if let Ok(child) = Command::new("node")
-                .arg(&srv)
-                .env("PORT", port.to_string())
-                .env("HOST", "127.0.0.1")
-                .envs(if allow_dev_unauth { vec![("ALLOW_DEV_UNAUTH", "1")] } else { vec![] as Vec<(&str,&str)> })
-                .envs(env_map.iter().map(|(k,v)| (k.as_str(), v.as_str())))
-                .current_dir(srv.parent().unwrap_or(&app_dir))
-                .stdout(Stdio::from(OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap())))
-                .stderr(Stdio::from(OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap())))
-                .spawn() {
-                tauri::async_runtime::block_on(async {
-                  let mut guard = SERVER_CHILD.lock().await;
-                  *guard = Some(child);
-                });
-                // Esperar readiness del puerto
-              } else {
-                let mut f2 = OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap());
-                let _ = writeln!(f2, "[GanadoAI] Error al iniciar 'node' del sistema");
-              }
+              let _ = writeln!(f, "[GanadoAI] Error al iniciar '{}': {}", node_bin, e);
             }
           }
-        } else {
-          // No hay binario preferido: intentar directamente con 'node' del sistema
-          if let Ok(child) = Command::new("node")
-            .arg(&srv)
-            .env("PORT", port.to_string())
-            .env("HOST", "127.0.0.1")
-            .envs(if allow_dev_unauth { vec![("ALLOW_DEV_UNAUTH", "1")] } else { vec![] as Vec<(&str,&str)> })
-            .envs(env_map.iter().map(|(k,v)| (k.as_str(), v.as_str())))
-            .current_dir(srv.parent().unwrap_or(&app_dir))
-            .stdout(Stdio::from(OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap())))
-            .stderr(Stdio::from(OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap())))
-            .spawn() {
-            tauri::async_runtime::block_on(async {
-              let mut guard = SERVER_CHILD.lock().await;
-              *guard = Some(child);
-            });
-            // Esperar readiness del puerto
-          } else {
-            let mut f3 = OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap());
-            let _ = writeln!(f3, "[GanadoAI] No se encontró 'node' para iniciar el servidor");
-          }
+        }
+        if !launched {
+          let mut f = OpenOptions::new().create(true).append(true).open(&log_path).unwrap_or_else(|_| File::create(&log_path).unwrap());
+          let _ = writeln!(f, "[GanadoAI] No se encontró 'node' para iniciar el servidor");
         }
         }
       } else {
@@ -761,6 +590,9 @@ fn main() {
 
       if let Some(win) = app.get_window("main") {
         let w = win.clone();
+        let token = instance_token.clone();
+        // started solo es true si se reutiliza el servidor de otra instancia
+        let owned = !started;
         tauri::async_runtime::spawn(async move {
           // Esperar readiness REAL del standalone: requerir 200 en '/'
           let client = reqwest::Client::new();
@@ -776,7 +608,8 @@ fn main() {
                 if resp.status().is_success() {
                   // Chequear cuerpo básico HTML para evitar redirección prematura con bundles no listos
                   if let Ok(text) = resp.text().await {
-                    if text.contains("<!DOCTYPE html") || text.contains("<html") {
+                    // y que quien responde sea nuestro servidor (handshake con el token)
+                    if (text.contains("<!DOCTYPE html") || text.contains("<html")) && ports::verify_node(port, &token).await {
                       ready = true;
                       break;
                     }
@@ -790,6 +623,8 @@ fn main() {
           }
 
           if ready {
+            services::mark_ready(&w.app_handle(), services::ServiceKind::Node).await;
+            ports::record(&w.app_handle(), services::ServiceKind::Node, port, owned);
             let _ = w.eval(&format!(
               "(function(){{
                 try{{
@@ -818,27 +653,22 @@ fn main() {
         let auto_model = std::env::var("AUTO_MODEL_SETUP").ok().map(|v| v=="1"||v.to_lowercase()=="true").unwrap_or(false);
         if auto_model {
         tauri::async_runtime::spawn(async move {
-          // Configuración por variables de entorno (opcional)
-          let model_url = std::env::var("NEXT_PUBLIC_MODEL_DOWNLOAD_URL").ok()
-            .or(Some("https://huggingface.co/ganado/ollama/resolve/main/DeepSeek-R1-Distill-Qwen-1.5B-Q8_0.gguf?download=true".to_string()));
+          // NEXT_PUBLIC_MODEL_DOWNLOAD_URL (y _SHA256) sustituye al modelo por
+          // defecto del catálogo firmado
+          let model_url = std::env::var("NEXT_PUBLIC_MODEL_DOWNLOAD_URL").ok();
           let model_sha = std::env::var("NEXT_PUBLIC_MODEL_SHA256").ok();
-          let llama_port: u16 = std::env::var("NEXT_PUBLIC_LLAMA_PORT").ok().and_then(|s| s.parse::<u16>().ok()).unwrap_or(11434);
 
           // Asegurar binario llama
-          let _ = download_llama_binary(app_handle.clone()).await;
-
-          // Determinar si ya existe un modelo
-          let models_dir = app_handle.path_resolver().app_data_dir().map(|p| p.join("models"));
-          let existing_model = models_dir.as_ref().and_then(|dir| std::fs::read_dir(dir).ok()).and_then(|mut rd| {
-            rd.find_map(|e| e.ok()).and_then(|e| {
-              let p = e.path();
-              if p.extension().and_then(|s| s.to_str()).unwrap_or("") == "gguf" { Some(p) } else { None }
-              })
-          });
+          let _ = llama_binary::download_llama_binary(app_handle.clone()).await;
+
+          // Si ya hay modelos, el que elige select_best_model: respeta el fijado por
+          // el usuario y no arranca uno que no quepa en memoria
+          let existing_model = models::select_best_model(&app_handle).ok();
 
           // Intentar copiar desde Resources/models si no hay modelo
-          let model_path_res = if let Some(p) = existing_model {
-            Ok(p.to_string_lossy().into_owned())
+          let model_path_res = if let Some(selection) = existing_model {
+            boot_log(&app_handle, format!("[tauri] modelo elegido {}: {}", selection.path, selection.reason)).await;
+            Ok(selection.path)
           } else {
             let resources_dir = app_handle.path_resolver().resource_dir();
             let bundled_model = resources_dir.as_ref().and_then(|rd| {
@@ -851,48 +681,52 @@ fn main() {
               })
             });
 
-            if let Some(src) = bundled_model {
-              if let Some(app_models) = models_dir.clone() {
-                let _ = std::fs::create_dir_all(&app_models);
-                let target = app_models.join(src.file_name().unwrap_or_else(|| std::ffi::OsStr::new("model.gguf")));
-                if std::fs::copy(&src, &target).is_ok() {
-                  Ok(target.to_string_lossy().into_owned())
-                } else if let Some(url) = model_url.clone() {
-                  download_model(url, model_sha.clone(), app_handle.clone(), app_handle.get_window("main").unwrap()).await
-                } else {
-                  Err("no model available".to_string())
+            // Igual que la copia del setup: preflight, .part verificado y rename a models/
+            let copied = match bundled_model {
+              Some(src) => {
+                let (h, s) = (app_handle.clone(), src.clone());
+                match tauri::async_runtime::spawn_blocking(move || sideload::install_bundled(&h, &s)).await.map_err(|e| e.to_string()).and_then(|r| r) {
+                  Ok(target) => Some(target.to_string_lossy().into_owned()),
+                  Err(e) => {
+                    boot_log(&app_handle, format!("[tauri] no se copia {}: {}", src.display(), e)).await;
+                    None
+                  }
                 }
-              } else if let Some(url) = model_url.clone() {
-                download_model(url, model_sha.clone(), app_handle.clone(), app_handle.get_window("main").unwrap()).await
-              } else {
-                Err("no model available".to_string())
               }
-            } else if let Some(url) = model_url.clone() {
-              download_model(url, model_sha.clone(), app_handle.clone(), app_handle.get_window("main").unwrap()).await
-            } else {
-              Err("no model url configured".to_string())
+              None => None,
+            };
+            match (copied, model_url) {
+              (Some(path), _) => Ok(path),
+              (None, Some(url)) => downloads::download_model(url, model_sha, None, None, app_handle.clone(), app_handle.get_window("main").unwrap()).await,
+              // Sin URL propia: el modelo por defecto del catálogo, con sha256 fijado
+              (None, None) => catalog::install_model(catalog::DEFAULT_MODEL_ID.to_string(), app_handle.clone(), app_handle.get_window("main").unwrap()).await,
             }
           };
 
+          // install_model puede fallar al crear el tag de Ollama con el GGUF ya descargado
+          let model_path_res = model_path_res.or_else(|e| models::select_best_model(&app_handle).map(|s| s.path).map_err(|_| e));
           if let Ok(model_path) = model_path_res {
-            let _ = start_llama_server(app_handle.clone(), model_path, llama_port).await;
+            let _ = llama_server::start_llama_server(app_handle.clone(), model_path, None).await;
           }
         });
         }
 
-        win.on_window_event(|event| {
+        let close_handle = app.app_handle();
+        win.on_window_event(move |event| {
           if let tauri::WindowEvent::CloseRequested { .. } = event {
-            tauri::async_runtime::block_on(async {
-              let mut guard = SERVER_CHILD.lock().await;
-              if let Some(child) = guard.as_mut() { let _ = child.kill(); }
-              *guard = None;
-            });
+            tauri::async_runtime::block_on(services::shutdown_all(&close_handle));
           }
         });
       }
 
       Ok(())
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|app, event| {
+      // También cubre salidas sin CloseRequested (dev, Cmd+Q, cierre del sistema)
+      if let tauri::RunEvent::Exit = event {
+        tauri::async_runtime::block_on(services::shutdown_all(app));
+      }
+    });
 } 
\ No newline at end of file
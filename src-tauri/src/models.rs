@@ -0,0 +1,316 @@
+use std::path::{Path, PathBuf};
+
+use tauri::Manager;
+
+use crate::gguf::{self, GgufInfo};
+use crate::ollama::{LocalModel, OllamaClient, OllamaError};
+use crate::{downloads, llama_server, profiles, settings};
+
+// Tag de Ollama por defecto mientras el usuario no elija otro
+pub const DEFAULT_MODEL_TAG: &str = "deepseek-r1-qwen-1_5b:latest";
+
+// Contexto con el que se estima la caché KV al decidir si un modelo cabe
+const SELECTION_CTX: u64 = 4096;
+// Memoria que se reserva para el sistema, el webview y el sidecar Node
+const RESERVED_RAM: u64 = 1536 * 1024 * 1024;
+// Buffers de cómputo y runtime de llama.cpp/Ollama además de pesos y KV
+pub const RUNTIME_OVERHEAD: u64 = 512 * 1024 * 1024;
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ModelCandidate {
+  pub path: String,
+  pub file_size: u64,
+  pub parameter_count: u64,
+  pub quantization: Option<String>,
+  pub estimated_ram: u64,
+  pub fits: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ModelSelection {
+  pub path: String,
+  pub reason: String,
+  pub pinned: bool,
+  pub total_ram: u64,
+  pub available_ram: u64,
+  pub candidates: Vec<ModelCandidate>,
+}
+
+// Directorios donde se buscan GGUF: app_data, Resources y (en dev) src-tauri/models
+pub fn model_dirs(app: &tauri::AppHandle) -> Vec<PathBuf> {
+  let mut dirs: Vec<PathBuf> = Vec::new();
+  if let Some(dir) = crate::app_data_dir(app) {
+    dirs.push(dir.join("models"));
+  }
+  if let Some(rd) = app.path_resolver().resource_dir() {
+    dirs.push(rd.join("models"));
+  }
+  if cfg!(debug_assertions) {
+    if let Ok(cwd) = std::env::current_dir() {
+      dirs.push(cwd.join("src-tauri").join("models"));
+    }
+  }
+  dirs
+}
+
+// Todos los .gguf válidos (header íntegro) de los directorios de modelos
+pub fn scan_models(app: &tauri::AppHandle) -> Vec<GgufInfo> {
+  let mut found = Vec::new();
+  for dir in model_dirs(app) {
+    if let Ok(rd) = std::fs::read_dir(&dir) {
+      for entry in rd.flatten() {
+        let p = entry.path();
+        if p.extension().and_then(|s| s.to_str()).unwrap_or("") == "gguf" {
+          // Descartar archivos truncados o que no son GGUF aunque tengan la extensión,
+          // y adaptadores LoRA, que no se cargan solos
+          if let Ok(info) = gguf::inspect(&p) {
+            if info.adapter_type.is_none() { found.push(info); }
+          }
+        }
+      }
+    }
+  }
+  found
+}
+
+// GGUF instalado cuyo sidecar registra `sha256`; primero se prueba `hint`
+pub fn find_by_digest(app: &tauri::AppHandle, sha256: &str, hint: Option<&str>) -> Option<String> {
+  let hint = hint.filter(|p| !p.is_empty()).map(str::to_string);
+  hint.into_iter().chain(scan_models(app).into_iter().map(|info| info.path))
+    .find(|p| downloads::read_digest_sidecar(Path::new(p)).as_deref() == Some(sha256))
+}
+
+// Bytes de pesos según parámetros y bits por peso (o el tamaño del archivo)
+pub fn weights_bytes(info: &GgufInfo) -> u64 {
+  if info.parameter_count > 0 && info.bits_per_weight > 0.0 {
+    (info.parameter_count as f64 * info.bits_per_weight / 8.0) as u64
+  } else {
+    info.file_size
+  }
+}
+
+// RAM estimada para servir el modelo: pesos, caché KV del contexto y overhead del runtime
+pub fn estimate_ram(info: &GgufInfo) -> u64 {
+  let weights = weights_bytes(info);
+  let ctx = info.context_length.unwrap_or(SELECTION_CTX).min(SELECTION_CTX);
+  // Sin metadata de arquitectura se aproxima la KV como un 10% de los pesos
+  let kv = info.kv_cache_bytes(ctx).unwrap_or(weights / 10);
+  weights + kv + RUNTIME_OVERHEAD
+}
+
+pub fn memory_snapshot() -> (u64, u64) {
+  let mut sys = sysinfo::System::new();
+  sys.refresh_memory();
+  (sys.total_memory(), sys.available_memory())
+}
+
+pub fn format_gb(bytes: u64) -> String {
+  format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+// Presupuesto: lo libre ahora menos la reserva, sin pasar del 75% de la RAM total
+pub fn ram_budget(total_ram: u64, available_ram: u64) -> u64 {
+  available_ram.saturating_sub(RESERVED_RAM).min(total_ram / 4 * 3)
+}
+
+pub fn select_best_model(app: &tauri::AppHandle) -> Result<ModelSelection, String> {
+  let (total_ram, available_ram) = memory_snapshot();
+  let infos = scan_models(app);
+  if infos.is_empty() { return Err("no local model found".into()); }
+
+  let budget = ram_budget(total_ram, available_ram);
+  let mut candidates: Vec<(ModelCandidate, &GgufInfo)> = infos.iter().map(|info| {
+    let estimated_ram = estimate_ram(info);
+    (ModelCandidate {
+      path: info.path.clone(),
+      file_size: info.file_size,
+      parameter_count: info.parameter_count,
+      quantization: info.quantization.clone(),
+      estimated_ram,
+      fits: estimated_ram <= budget,
+    }, info)
+  }).collect();
+  // Más parámetros primero; a igualdad, la cuantización de mayor calidad
+  candidates.sort_by(|(a, ai), (b, bi)| {
+    b.parameter_count.cmp(&a.parameter_count)
+      .then(bi.bits_per_weight.partial_cmp(&ai.bits_per_weight).unwrap_or(std::cmp::Ordering::Equal))
+  });
+
+  let pinned = settings::load(app).pinned_model;
+  let pinned_candidate = pinned.as_ref().and_then(|p| candidates.iter().find(|(c, _)| &c.path == p));
+  let (chosen, reason, is_pinned) = if let Some((c, _)) = pinned_candidate {
+    let reason = if c.fits {
+      "modelo fijado por el usuario".to_string()
+    } else {
+      format!("modelo fijado por el usuario (requiere ~{}, hay {} disponibles)", format_gb(c.estimated_ram), format_gb(budget))
+    };
+    (c.clone(), reason, true)
+  } else if let Some((c, _)) = candidates.iter().find(|(c, _)| c.fits) {
+    let reason = format!("el modelo más capaz que cabe en memoria (~{} de {} disponibles)", format_gb(c.estimated_ram), format_gb(budget));
+    (c.clone(), reason, false)
+  } else {
+    // Nada cabe: el de menor consumo es el que menos castiga al equipo
+    let (c, _) = candidates.iter().min_by_key(|(c, _)| c.estimated_ram).ok_or("no local model found")?;
+    let reason = format!("ningún modelo cabe en la memoria libre ({}); se usa el más liviano (~{})", format_gb(budget), format_gb(c.estimated_ram));
+    (c.clone(), reason, false)
+  };
+
+  Ok(ModelSelection {
+    path: chosen.path,
+    reason,
+    pinned: is_pinned,
+    total_ram,
+    available_ram,
+    candidates: candidates.into_iter().map(|(c, _)| c).collect(),
+  })
+}
+
+#[tauri::command]
+pub fn find_available_model(app: tauri::AppHandle) -> Result<String, String> {
+  select_best_model(&app).map(|s| s.path)
+}
+
+#[tauri::command]
+pub fn select_model(app: tauri::AppHandle) -> Result<ModelSelection, String> {
+  select_best_model(&app)
+}
+
+#[tauri::command]
+pub fn pin_model(app: tauri::AppHandle, path: Option<String>) -> Result<(), String> {
+  if let Some(p) = path.as_ref() {
+    gguf::inspect(std::path::Path::new(p))?;
+  }
+  settings::update(&app, |s| s.pinned_model = path)?;
+  Ok(())
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LocalGguf {
+  #[serde(flatten)]
+  pub info: GgufInfo,
+  // Del sidecar .sha256; None si aún no se calculó
+  pub sha256: Option<String>,
+  pub estimated_ram: u64,
+  pub pinned: bool,
+  // Tags de Ollama creados desde este archivo
+  pub ollama_tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ModelInventory {
+  pub default_model: String,
+  pub ggufs: Vec<LocalGguf>,
+  pub ollama: Vec<LocalModel>,
+  // Motivo por el que no se pudo consultar Ollama (apagado, etc.)
+  pub ollama_error: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LoadedModel {
+  pub name: String,
+  // "ollama" o "llama"
+  pub backend: String,
+  // Memoria total que ocupa (RAM + VRAM); en llama-server es la estimación
+  pub size: u64,
+  pub size_vram: u64,
+  pub expires_at: Option<String>,
+  pub path: Option<String>,
+}
+
+// Tag que se prepara al arrancar: el elegido con set_default_model o el integrado
+pub fn default_model(app: &tauri::AppHandle) -> String {
+  settings::load(app).default_model.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| DEFAULT_MODEL_TAG.to_string())
+}
+
+#[tauri::command]
+pub async fn list_local_models(app: tauri::AppHandle) -> Result<ModelInventory, String> {
+  let scan_app = app.clone();
+  let infos = tauri::async_runtime::spawn_blocking(move || scan_models(&scan_app)).await.map_err(|e| e.to_string())?;
+  let pinned = settings::load(&app).pinned_model;
+  let applied = profiles::applied_all(&app);
+  let ggufs = infos.into_iter().map(|info| {
+    let sha256 = downloads::read_digest_sidecar(Path::new(&info.path));
+    let mut ollama_tags: Vec<String> = applied.iter()
+      .filter(|(_, a)| sha256.as_deref() == Some(a.gguf_sha256.as_str()))
+      .map(|(tag, _)| tag.clone())
+      .collect();
+    ollama_tags.sort();
+    LocalGguf {
+      estimated_ram: estimate_ram(&info),
+      pinned: pinned.as_deref() == Some(info.path.as_str()),
+      sha256,
+      ollama_tags,
+      info,
+    }
+  }).collect();
+  let (ollama, ollama_error) = match OllamaClient::from_state() {
+    Ok(client) => match client.tags().await {
+      Ok(tags) => (tags, None),
+      Err(e) => (vec![], Some(e.to_string())),
+    },
+    Err(e) => (vec![], Some(e.to_string())),
+  };
+  Ok(ModelInventory { default_model: default_model(&app), ggufs, ollama, ollama_error })
+}
+
+#[tauri::command]
+pub async fn loaded_models() -> Result<Vec<LoadedModel>, String> {
+  let mut loaded: Vec<LoadedModel> = Vec::new();
+  if let Ok(client) = OllamaClient::from_state() {
+    // Ollama caído no tiene modelos cargados; llama-server se informa igual
+    let running = match client.ps().await {
+      Err(OllamaError::Unreachable { .. }) => vec![],
+      other => other?,
+    };
+    for m in running {
+      loaded.push(LoadedModel {
+        name: m.name,
+        backend: "ollama".into(),
+        size: m.size,
+        size_vram: m.size_vram,
+        expires_at: Some(m.expires_at),
+        path: None,
+      });
+    }
+  }
+  if let Some(path) = llama_server::current_model() {
+    let size = gguf::inspect(Path::new(&path)).map(|info| estimate_ram(&info)).unwrap_or(0);
+    let name = Path::new(&path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| path.clone());
+    loaded.push(LoadedModel { name, backend: "llama".into(), size, size_vram: 0, expires_at: None, path: Some(path) });
+  }
+  Ok(loaded)
+}
+
+// Libera la memoria del modelo sin desinstalarlo. Para llama-server `tag` es el
+// nombre o la ruta del GGUF cargado y se detiene el servidor.
+#[tauri::command]
+pub async fn unload_model(tag: String, app: tauri::AppHandle) -> Result<(), String> {
+  if let Some(path) = llama_server::current_model() {
+    let name = Path::new(&path).file_name().map(|n| n.to_string_lossy().into_owned());
+    if tag == path || Some(&tag) == name.as_ref() {
+      return llama_server::stop_llama_server(app).await;
+    }
+  }
+  OllamaClient::from_state()?.unload(&tag).await?;
+  Ok(())
+}
+
+#[tauri::command]
+pub fn get_default_model(app: tauri::AppHandle) -> String {
+  default_model(&app)
+}
+
+// Fija el tag por defecto; si Ollama está arriba, el tag debe existir
+#[tauri::command]
+pub async fn set_default_model(tag: String, app: tauri::AppHandle) -> Result<String, String> {
+  let tag = tag.trim().to_string();
+  if tag.is_empty() { return Err("empty model tag".into()); }
+  if let Ok(client) = OllamaClient::from_state() {
+    if !client.has_model(&tag).await? {
+      return Err(format!("el modelo '{}' no está instalado en Ollama", tag));
+    }
+  }
+  settings::update(&app, |s| s.default_model = Some(tag.clone()))?;
+  let _ = app.emit_all("default-model-changed", serde_json::json!({ "tag": tag }));
+  Ok(tag)
+}
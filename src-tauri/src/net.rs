@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::time::sleep;
+
+// Capa común de descargas: lista ordenada de mirrors, reintentos con backoff
+// exponencial + jitter y timeouts de conexión/lectura.
+
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+  pub attempts_per_mirror: u32,
+  pub base_delay: Duration,
+  pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    RetryPolicy {
+      attempts_per_mirror: 4,
+      base_delay: Duration::from_millis(500),
+      max_delay: Duration::from_secs(30),
+    }
+  }
+}
+
+impl RetryPolicy {
+  // Backoff exponencial con jitter en [50%, 100%] del retardo nominal
+  pub fn delay(&self, attempt: u32) -> Duration {
+    let nominal = self.base_delay.saturating_mul(1u32 << attempt.min(16)).min(self.max_delay);
+    nominal.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MirrorError {
+  pub url: String,
+  pub attempt: u32,
+  pub error: String,
+}
+
+pub fn format_errors(errors: &[MirrorError]) -> String {
+  if errors.is_empty() { return "no mirrors configured".into(); }
+  let parts: Vec<String> = errors.iter().map(|e| format!("{} (intento {}): {}", e.url, e.attempt, e.error)).collect();
+  format!("all mirrors failed: {}", parts.join("; "))
+}
+
+// Cliente sin timeout total (las descargas duran minutos) pero con límites de
+// conexión y de inactividad de lectura
+pub fn download_client() -> Result<reqwest::Client, String> {
+  reqwest::Client::builder()
+    .connect_timeout(Duration::from_secs(10))
+    .read_timeout(Duration::from_secs(30))
+    .build()
+    .map_err(|e| e.to_string())
+}
+
+// URL principal + mirrors explícitos + bases de MODEL_MIRROR_BASES (separadas
+// por coma, p. ej. CDN propio o un host de la LAN) con el nombre de archivo
+pub fn mirror_urls(primary: &str, extra: &[String], file_name: &str) -> Vec<String> {
+  let mut urls = vec![primary.to_string()];
+  urls.extend(extra.iter().cloned());
+  if let Ok(bases) = std::env::var("MODEL_MIRROR_BASES") {
+    for base in bases.split(',').map(str::trim).filter(|b| !b.is_empty()) {
+      urls.push(format!("{}/{}", base.trim_end_matches('/'), file_name));
+    }
+  }
+  let mut seen = std::collections::HashSet::new();
+  urls.retain(|u| seen.insert(u.clone()));
+  urls
+}
+
+fn retryable_status(status: reqwest::StatusCode) -> bool {
+  status.is_server_error() || status == reqwest::StatusCode::REQUEST_TIMEOUT || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+// Un intento contra un mirror; el error indica si vale la pena reintentar
+async fn try_once(client: &reqwest::Client, url: &str, offset: u64, if_range: Option<&str>) -> Result<reqwest::Response, (String, bool)> {
+  let mut req = client.get(url);
+  if offset > 0 {
+    req = req.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    // Si el objeto cambió en el servidor, responde 200 con el archivo entero
+    if let Some(validator) = if_range { req = req.header(reqwest::header::IF_RANGE, validator); }
+  }
+  match req.send().await {
+    Ok(res) if res.status().is_success() => Ok(res),
+    // 416 se devuelve al llamador: con offset > 0 indica archivo ya completo
+    Ok(res) if offset > 0 && res.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE => Ok(res),
+    Ok(res) => Err((format!("HTTP {}", res.status()), retryable_status(res.status()))),
+    Err(e) => Err((e.to_string(), true)),
+  }
+}
+
+// Abre una respuesta desde el primer mirror que conteste, pidiendo desde
+// `offset` con Range si es > 0 (condicionado a `if_range`, un ETag o
+// Last-Modified). `on_error` recibe cada fallo individual.
+pub async fn open_with_mirrors<F: FnMut(&MirrorError)>(
+  client: &reqwest::Client,
+  mirrors: &[String],
+  policy: &RetryPolicy,
+  offset: u64,
+  if_range: Option<&str>,
+  mut on_error: F,
+) -> Result<(reqwest::Response, String), Vec<MirrorError>> {
+  let mut errors = Vec::new();
+  for url in mirrors {
+    for attempt in 0..policy.attempts_per_mirror {
+      if attempt > 0 { sleep(policy.delay(attempt - 1)).await; }
+      match try_once(client, url, offset, if_range).await {
+        Ok(res) => return Ok((res, url.clone())),
+        Err((error, retry)) => {
+          let err = MirrorError { url: url.clone(), attempt: attempt + 1, error };
+          on_error(&err);
+          errors.push(err);
+          // Un 404/403 no mejora reintentando: pasar al siguiente mirror
+          if !retry { break; }
+        }
+      }
+    }
+  }
+  Err(errors)
+}
+
+// Descarga completa a memoria (binarios, catálogos) con reintentos, incluyendo
+// fallos al leer el cuerpo
+pub async fn fetch_bytes<F: FnMut(&MirrorError)>(
+  client: &reqwest::Client,
+  mirrors: &[String],
+  policy: &RetryPolicy,
+  mut on_error: F,
+) -> Result<(Vec<u8>, String), Vec<MirrorError>> {
+  let mut errors = Vec::new();
+  for url in mirrors {
+    for attempt in 0..policy.attempts_per_mirror {
+      if attempt > 0 { sleep(policy.delay(attempt - 1)).await; }
+      let (error, retry) = match try_once(client, url, 0, None).await {
+        Ok(res) => match res.bytes().await {
+          Ok(bytes) => return Ok((bytes.to_vec(), url.clone())),
+          Err(e) => (e.to_string(), true),
+        },
+        Err(e) => e,
+      };
+      let err = MirrorError { url: url.clone(), attempt: attempt + 1, error };
+      on_error(&err);
+      errors.push(err);
+      if !retry { break; }
+    }
+  }
+  Err(errors)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn policy() -> RetryPolicy {
+    RetryPolicy { attempts_per_mirror: 4, base_delay: Duration::from_millis(100), max_delay: Duration::from_secs(2) }
+  }
+
+  #[test]
+  fn delay_stays_within_jitter_bounds() {
+    let p = policy();
+    for attempt in 0..4 {
+      let nominal = Duration::from_millis(100 << attempt);
+      for _ in 0..200 {
+        let d = p.delay(attempt);
+        assert!(d >= nominal / 2 && d <= nominal, "attempt {}: {:?}", attempt, d);
+      }
+    }
+  }
+
+  #[test]
+  fn delay_is_capped_at_max_delay() {
+    let p = policy();
+    for attempt in [5, 16, 31, u32::MAX] {
+      let d = p.delay(attempt);
+      assert!(d >= p.max_delay / 2 && d <= p.max_delay, "attempt {}: {:?}", attempt, d);
+    }
+  }
+
+  #[test]
+  fn formats_mirror_errors() {
+    assert_eq!(format_errors(&[]), "no mirrors configured");
+    let errors = [MirrorError { url: "http://a".into(), attempt: 2, error: "HTTP 503".into() }];
+    assert_eq!(format_errors(&errors), "all mirrors failed: http://a (intento 2): HTTP 503");
+  }
+}
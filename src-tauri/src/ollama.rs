@@ -0,0 +1,426 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tauri::Manager;
+use tokio::io::AsyncReadExt;
+
+use crate::downloads;
+use crate::ports;
+use crate::services::ServiceKind;
+
+// Cliente tipado de la API HTTP de Ollama (create, delete, pull, show, ps) en
+// lugar de invocar el CLI. Las operaciones largas reenvían su progreso como
+// eventos `ollama-model-progress`.
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// Primera versión cuyo /api/create acepta `files`/`adapters` con digests de blobs;
+// las anteriores solo entienden un Modelfile
+const FILES_CREATE_VERSION: (u64, u64, u64) = (0, 5, 5);
+const UPLOAD_CHUNK: usize = 1024 * 1024;
+// Emite progreso de subida como mucho cada 16 MB
+const UPLOAD_PROGRESS_STEP: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum OllamaError {
+  // start_ollama_server todavía no registró un endpoint
+  NotRunning,
+  Unreachable { url: String, message: String },
+  NotFound { model: String },
+  Http { status: u16, message: String },
+  // Error informado por Ollama dentro de una respuesta en streaming
+  Api { message: String },
+  Io { message: String },
+}
+
+impl fmt::Display for OllamaError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      OllamaError::NotRunning => write!(f, "ollama is not running; start it first"),
+      OllamaError::Unreachable { url, message } => write!(f, "cannot reach ollama at {}: {}", url, message),
+      OllamaError::NotFound { model } => write!(f, "model '{}' not found in ollama", model),
+      OllamaError::Http { status, message } => write!(f, "ollama returned HTTP {}: {}", status, message),
+      OllamaError::Api { message } => write!(f, "ollama error: {}", message),
+      OllamaError::Io { message } => write!(f, "{}", message),
+    }
+  }
+}
+
+impl From<OllamaError> for String {
+  fn from(e: OllamaError) -> String { e.to_string() }
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct CreateRequest {
+  pub model: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub from: Option<String>,
+  // nombre de archivo -> "sha256:<digest>" de blobs ya subidos
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub files: Option<HashMap<String, String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub adapters: Option<HashMap<String, String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub template: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub system: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub parameters: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ModelDetails {
+  pub format: String,
+  pub family: String,
+  pub parameter_size: String,
+  pub quantization_level: String,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ShowResponse {
+  pub modelfile: String,
+  pub parameters: String,
+  pub template: String,
+  pub system: String,
+  pub details: ModelDetails,
+  pub model_info: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LocalModel {
+  pub name: String,
+  pub model: String,
+  pub size: u64,
+  pub digest: String,
+  pub modified_at: String,
+  pub details: ModelDetails,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RunningModel {
+  pub name: String,
+  pub model: String,
+  pub size: u64,
+  pub size_vram: u64,
+  pub digest: String,
+  pub expires_at: String,
+  pub details: ModelDetails,
+}
+
+impl CreateRequest {
+  // Modelfile con los blobs ya subidos referenciados como `@sha256:<digest>`
+  pub fn modelfile(&self) -> String {
+    let blob = |digest: &String| format!("@{}", digest);
+    let from = self.from.clone().or_else(|| self.files.iter().flat_map(|f| f.values()).next().map(blob)).unwrap_or_default();
+    let adapters: Vec<String> = self.adapters.iter().flat_map(|a| a.values()).map(blob).collect();
+    crate::profiles::render_modelfile(&from, &adapters, self.template.as_deref(), self.system.as_deref(), self.parameters.iter().flatten())
+  }
+}
+
+// "0.5.7", "v0.6.0-rc1" -> (mayor, menor, parche)
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+  let core = version.trim().trim_start_matches('v').split(['-', '+']).next()?;
+  let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+  Some((parts.next()??, parts.next().flatten().unwrap_or(0), parts.next().flatten().unwrap_or(0)))
+}
+
+// Destino de los eventos de progreso de una operación sobre un modelo
+#[derive(Clone)]
+pub struct ProgressSink {
+  app: tauri::AppHandle,
+  model: String,
+}
+
+impl ProgressSink {
+  pub fn new(app: &tauri::AppHandle, model: &str) -> Self {
+    ProgressSink { app: app.clone(), model: model.to_string() }
+  }
+
+  pub fn emit(&self, operation: &str, status: &str, digest: Option<&str>, completed: Option<u64>, total: Option<u64>) {
+    let _ = self.app.emit_all("ollama-model-progress", serde_json::json!({
+      "model": self.model,
+      "operation": operation,
+      "status": status,
+      "digest": digest,
+      "completed": completed,
+      "total": total,
+    }));
+  }
+}
+
+pub struct OllamaClient {
+  base: String,
+  http: reqwest::Client,
+}
+
+impl OllamaClient {
+  pub fn new(base: &str) -> Result<Self, OllamaError> {
+    let http = reqwest::Client::builder()
+      .connect_timeout(Duration::from_secs(5))
+      .build()
+      .map_err(|e| OllamaError::Io { message: e.to_string() })?;
+    Ok(OllamaClient { base: base.trim_end_matches('/').to_string(), http })
+  }
+
+  // Cliente para el Ollama que arrancó (o reutilizó) start_ollama_server
+  pub fn from_state() -> Result<Self, OllamaError> {
+    let endpoint = ports::get(ServiceKind::Ollama).ok_or(OllamaError::NotRunning)?;
+    Self::new(&endpoint.url)
+  }
+
+  pub fn url(&self, path: &str) -> String {
+    format!("{}{}", self.base, path)
+  }
+
+  fn unreachable(&self, e: reqwest::Error) -> OllamaError {
+    OllamaError::Unreachable { url: self.base.clone(), message: e.to_string() }
+  }
+
+  // Convierte una respuesta no exitosa en error leyendo {"error": "..."}
+  async fn check(&self, res: reqwest::Response, model: Option<&str>) -> Result<reqwest::Response, OllamaError> {
+    let status = res.status();
+    if status.is_success() { return Ok(res); }
+    let body = res.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body).ok()
+      .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string))
+      .unwrap_or(body);
+    if status == reqwest::StatusCode::NOT_FOUND {
+      if let Some(model) = model { return Err(OllamaError::NotFound { model: model.to_string() }); }
+    }
+    Err(OllamaError::Http { status: status.as_u16(), message })
+  }
+
+  // Lee una respuesta NDJSON hasta "success", reenviando cada línea como progreso
+  async fn follow(&self, res: reqwest::Response, operation: &str, sink: &ProgressSink) -> Result<(), OllamaError> {
+    let mut stream = res.bytes_stream();
+    let mut buf: Vec<u8> = Vec::new();
+    let mut succeeded = false;
+    while let Some(chunk) = stream.next().await {
+      let chunk = chunk.map_err(|e| self.unreachable(e))?;
+      buf.extend_from_slice(&chunk);
+      while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+        let line: Vec<u8> = buf.drain(..=pos).collect();
+        let Ok(event) = serde_json::from_slice::<serde_json::Value>(&line) else { continue };
+        if let Some(message) = event.get("error").and_then(|e| e.as_str()) {
+          sink.emit(operation, "failed", None, None, None);
+          return Err(OllamaError::Api { message: message.to_string() });
+        }
+        let status = event.get("status").and_then(|s| s.as_str()).unwrap_or_default();
+        sink.emit(
+          operation,
+          status,
+          event.get("digest").and_then(|d| d.as_str()),
+          event.get("completed").and_then(|c| c.as_u64()),
+          event.get("total").and_then(|t| t.as_u64()),
+        );
+        if status == "success" { succeeded = true; }
+      }
+    }
+    if succeeded { Ok(()) } else { Err(OllamaError::Api { message: format!("{} ended without success", operation) }) }
+  }
+
+  pub async fn tags(&self) -> Result<Vec<LocalModel>, OllamaError> {
+    let res = self.http.get(self.url("/api/tags")).timeout(REQUEST_TIMEOUT).send().await.map_err(|e| self.unreachable(e))?;
+    let res = self.check(res, None).await?;
+    let body: serde_json::Value = res.json().await.map_err(|e| OllamaError::Io { message: e.to_string() })?;
+    // Un esquema distinto no es "sin modelos": avisar en vez de devolver lista vacía
+    serde_json::from_value(body.get("models").cloned().unwrap_or_default())
+      .map_err(|e| OllamaError::Io { message: format!("unexpected /api/tags response: {}", e) })
+  }
+
+  // "modelo" equivale a "modelo:latest"
+  pub async fn has_model(&self, tag: &str) -> Result<bool, OllamaError> {
+    let full = if tag.contains(':') { tag.to_string() } else { format!("{}:latest", tag) };
+    Ok(self.tags().await?.iter().any(|m| m.name == tag || m.name == full))
+  }
+
+  pub async fn show(&self, model: &str) -> Result<ShowResponse, OllamaError> {
+    let res = self.http.post(self.url("/api/show"))
+      .json(&serde_json::json!({ "model": model }))
+      .timeout(REQUEST_TIMEOUT)
+      .send().await.map_err(|e| self.unreachable(e))?;
+    let res = self.check(res, Some(model)).await?;
+    res.json().await.map_err(|e| OllamaError::Io { message: e.to_string() })
+  }
+
+  // Chat sin streaming; devuelve el texto de la respuesta
+  pub async fn chat_once(&self, model: &str, prompt: &str, timeout: Duration) -> Result<String, OllamaError> {
+    let res = self.http.post(self.url("/api/chat"))
+      .json(&serde_json::json!({
+        "model": model,
+        "messages": [{ "role": "user", "content": prompt }],
+        "stream": false,
+      }))
+      .timeout(timeout)
+      .send().await.map_err(|e| self.unreachable(e))?;
+    let res = self.check(res, Some(model)).await?;
+    let body: serde_json::Value = res.json().await.map_err(|e| OllamaError::Io { message: e.to_string() })?;
+    Ok(body.pointer("/message/content").and_then(|c| c.as_str()).unwrap_or_default().to_string())
+  }
+
+  // Abre /api/chat en streaming; el llamador lee el NDJSON y cortar la conexión
+  // detiene la generación en Ollama
+  pub async fn chat_stream(&self, body: &serde_json::Value) -> Result<reqwest::Response, OllamaError> {
+    let model = body.get("model").and_then(|m| m.as_str()).unwrap_or_default().to_string();
+    let res = self.http.post(self.url("/api/chat"))
+      .json(body)
+      .send().await.map_err(|e| self.unreachable(e))?;
+    self.check(res, Some(&model)).await
+  }
+
+  // Descarga el modelo de memoria (keep_alive 0) sin borrarlo
+  pub async fn unload(&self, model: &str) -> Result<(), OllamaError> {
+    let res = self.http.post(self.url("/api/generate"))
+      .json(&serde_json::json!({ "model": model, "keep_alive": 0 }))
+      .timeout(REQUEST_TIMEOUT)
+      .send().await.map_err(|e| self.unreachable(e))?;
+    self.check(res, Some(model)).await?;
+    Ok(())
+  }
+
+  pub async fn ps(&self) -> Result<Vec<RunningModel>, OllamaError> {
+    let res = self.http.get(self.url("/api/ps")).timeout(REQUEST_TIMEOUT).send().await.map_err(|e| self.unreachable(e))?;
+    let res = self.check(res, None).await?;
+    let body: serde_json::Value = res.json().await.map_err(|e| OllamaError::Io { message: e.to_string() })?;
+    serde_json::from_value(body.get("models").cloned().unwrap_or_default())
+      .map_err(|e| OllamaError::Io { message: format!("unexpected /api/ps response: {}", e) })
+  }
+
+  pub async fn delete(&self, model: &str) -> Result<(), OllamaError> {
+    let res = self.http.delete(self.url("/api/delete"))
+      .json(&serde_json::json!({ "model": model }))
+      .timeout(REQUEST_TIMEOUT)
+      .send().await.map_err(|e| self.unreachable(e))?;
+    self.check(res, Some(model)).await.map(|_| ())
+  }
+
+  pub async fn pull(&self, model: &str, sink: &ProgressSink) -> Result<(), OllamaError> {
+    let res = self.http.post(self.url("/api/pull"))
+      .json(&serde_json::json!({ "model": model, "stream": true }))
+      .send().await.map_err(|e| self.unreachable(e))?;
+    let res = self.check(res, Some(model)).await?;
+    self.follow(res, "pull", sink).await
+  }
+
+  pub async fn create(&self, req: &CreateRequest, sink: &ProgressSink) -> Result<(), OllamaError> {
+    let mut body = serde_json::to_value(req).map_err(|e| OllamaError::Io { message: e.to_string() })?;
+    body["stream"] = serde_json::Value::Bool(true);
+    let res = self.http.post(self.url("/api/create")).json(&body).send().await.map_err(|e| self.unreachable(e))?;
+    let res = self.check(res, None).await?;
+    self.follow(res, "create", sink).await
+  }
+
+  // Modelfile equivalente a `req` para Ollama anterior a FILES_CREATE_VERSION
+  pub async fn create_from_modelfile(&self, req: &CreateRequest, sink: &ProgressSink) -> Result<(), OllamaError> {
+    let body = serde_json::json!({ "model": req.model, "name": req.model, "modelfile": req.modelfile(), "stream": true });
+    let res = self.http.post(self.url("/api/create")).json(&body).send().await.map_err(|e| self.unreachable(e))?;
+    let res = self.check(res, None).await?;
+    self.follow(res, "create", sink).await
+  }
+
+  // Versión del servidor según /api/version ("0.5.7", "0.6.0-rc1", ...)
+  pub async fn version(&self) -> Result<String, OllamaError> {
+    let res = self.http.get(self.url("/api/version")).timeout(REQUEST_TIMEOUT).send().await.map_err(|e| self.unreachable(e))?;
+    let res = self.check(res, None).await?;
+    let body: serde_json::Value = res.json().await.map_err(|e| OllamaError::Io { message: e.to_string() })?;
+    body.get("version").and_then(|v| v.as_str()).map(str::to_string)
+      .ok_or_else(|| OllamaError::Io { message: "unexpected /api/version response".into() })
+  }
+
+  pub async fn has_blob(&self, digest: &str) -> Result<bool, OllamaError> {
+    let res = self.http.head(self.url(&format!("/api/blobs/sha256:{}", digest)))
+      .timeout(REQUEST_TIMEOUT)
+      .send().await.map_err(|e| self.unreachable(e))?;
+    Ok(res.status().is_success())
+  }
+
+  // Sube un archivo como blob; Ollama verifica que el contenido coincida con el digest
+  pub async fn push_blob(&self, path: &Path, digest: &str, sink: &ProgressSink) -> Result<(), OllamaError> {
+    let io_err = |e: std::io::Error| OllamaError::Io { message: format!("cannot read {}: {}", path.display(), e) };
+    let file = tokio::fs::File::open(path).await.map_err(io_err)?;
+    let total = file.metadata().await.map_err(io_err)?.len();
+    let digest_label = format!("sha256:{}", digest);
+    sink.emit("upload", "uploading", Some(&digest_label), Some(0), Some(total));
+    let progress = (sink.clone(), digest_label.clone());
+    let body = futures_util::stream::unfold((file, 0u64, 0u64, progress), move |(mut file, sent, last, progress)| async move {
+      let mut buf = vec![0u8; UPLOAD_CHUNK];
+      match file.read(&mut buf).await {
+        Ok(0) => None,
+        Ok(n) => {
+          buf.truncate(n);
+          let sent = sent + n as u64;
+          let last = if sent - last >= UPLOAD_PROGRESS_STEP || sent == total {
+            progress.0.emit("upload", "uploading", Some(&progress.1), Some(sent), Some(total));
+            sent
+          } else { last };
+          Some((Ok::<Vec<u8>, std::io::Error>(buf), (file, sent, last, progress)))
+        }
+        Err(e) => Some((Err(e), (file, sent, last, progress))),
+      }
+    });
+    let res = self.http.post(self.url(&format!("/api/blobs/{}", digest_label)))
+      .header(reqwest::header::CONTENT_LENGTH, total)
+      .body(reqwest::Body::wrap_stream(body))
+      .send().await.map_err(|e| self.unreachable(e))?;
+    self.check(res, None).await?;
+    sink.emit("upload", "success", Some(&digest_label), Some(total), Some(total));
+    Ok(())
+  }
+
+  // Sube un archivo local si Ollama aún no tiene su blob; devuelve (nombre, digest)
+  async fn ensure_blob(&self, path: &Path, sink: &ProgressSink) -> Result<(String, String), OllamaError> {
+    let owned = path.to_path_buf();
+    let digest = tauri::async_runtime::spawn_blocking(move || downloads::model_digest(&owned)).await
+      .map_err(|e| OllamaError::Io { message: e.to_string() })?
+      .map_err(|message| OllamaError::Io { message })?;
+    if !self.has_blob(&digest).await? {
+      self.push_blob(path, &digest, sink).await?;
+    }
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| "model.gguf".into());
+    Ok((file_name, digest))
+  }
+
+  // Registra un GGUF local como `base.model`: sube los blobs que falten (modelo y
+  // adaptadores) y crea el modelo con el system/template/parameters de `base`
+  pub async fn import_gguf(&self, mut base: CreateRequest, gguf: &Path, adapters: &[PathBuf], sink: &ProgressSink) -> Result<String, OllamaError> {
+    let (file_name, digest) = self.ensure_blob(gguf, sink).await?;
+    base.files = Some(HashMap::from([(file_name, format!("sha256:{}", digest))]));
+    if !adapters.is_empty() {
+      let mut map = HashMap::new();
+      for adapter in adapters {
+        let (name, adapter_digest) = self.ensure_blob(adapter, sink).await?;
+        map.insert(name, format!("sha256:{}", adapter_digest));
+      }
+      base.adapters = Some(map);
+    }
+    // Las compilaciones de desarrollo informan 0.0.0 y ya usan la API nueva
+    let version = self.version().await?;
+    match parse_version(&version) {
+      Some(v) if v != (0, 0, 0) && v < FILES_CREATE_VERSION => self.create_from_modelfile(&base, sink).await?,
+      _ => self.create(&base, sink).await?,
+    }
+    Ok(digest)
+  }
+}
+
+#[tauri::command]
+pub async fn pull_ollama_model(tag: String, app: tauri::AppHandle) -> Result<(), OllamaError> {
+  OllamaClient::from_state()?.pull(&tag, &ProgressSink::new(&app, &tag)).await
+}
+
+#[tauri::command]
+pub async fn delete_ollama_model(tag: String) -> Result<(), OllamaError> {
+  OllamaClient::from_state()?.delete(&tag).await
+}
+
+#[tauri::command]
+pub async fn show_ollama_model(tag: String) -> Result<ShowResponse, OllamaError> {
+  OllamaClient::from_state()?.show(&tag).await
+}
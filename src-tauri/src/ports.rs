@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+
+use crate::services::ServiceKind;
+
+// Puerto de cada servicio elegido al arrancar. Se prefiere el configurado
+// (NEXT_PORT, NEXT_PUBLIC_LLAMA_PORT) y, si otro programa lo ocupa, se pide uno
+// libre al sistema. Antes de reutilizar un listener ajeno se comprueba que sea
+// realmente nuestro (Node con handshake de token) o un Ollama.
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ServiceEndpoint {
+  pub port: u16,
+  pub url: String,
+  // false si el proceso no lo lanzó esta app (otra instancia u Ollama del sistema)
+  pub owned: bool,
+}
+
+static ENDPOINTS: Lazy<RwLock<HashMap<ServiceKind, ServiceEndpoint>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+// Ruta que recibe el servidor Node (GANADO_ENDPOINTS_FILE) para encontrar a Ollama
+pub fn endpoints_file(app: &tauri::AppHandle) -> Option<PathBuf> {
+  crate::app_data_dir(app).map(|d| d.join("run").join("endpoints.json"))
+}
+
+fn persist(app: &tauri::AppHandle, snapshot: &HashMap<ServiceKind, ServiceEndpoint>) {
+  if let Some(path) = endpoints_file(app) {
+    if let Some(dir) = path.parent() { let _ = std::fs::create_dir_all(dir); }
+    if let Ok(text) = serde_json::to_string_pretty(snapshot) { let _ = std::fs::write(path, text); }
+  }
+}
+
+pub fn record(app: &tauri::AppHandle, kind: ServiceKind, port: u16, owned: bool) {
+  let endpoint = ServiceEndpoint { port, url: format!("http://127.0.0.1:{}", port), owned };
+  let snapshot = {
+    let mut guard = ENDPOINTS.write().unwrap_or_else(|e| e.into_inner());
+    guard.insert(kind, endpoint);
+    guard.clone()
+  };
+  persist(app, &snapshot);
+}
+
+// El servicio dejó de estar disponible (detenido o en bucle de fallos)
+pub fn forget(app: &tauri::AppHandle, kind: ServiceKind) {
+  let snapshot = {
+    let mut guard = ENDPOINTS.write().unwrap_or_else(|e| e.into_inner());
+    if guard.remove(&kind).is_none() { return; }
+    guard.clone()
+  };
+  persist(app, &snapshot);
+}
+
+// Al arrancar: los puertos de la ejecución anterior ya no valen
+pub fn reset(app: &tauri::AppHandle) {
+  ENDPOINTS.write().unwrap_or_else(|e| e.into_inner()).clear();
+  if let Some(path) = endpoints_file(app) { let _ = std::fs::remove_file(path); }
+}
+
+pub fn get(kind: ServiceKind) -> Option<ServiceEndpoint> {
+  ENDPOINTS.read().unwrap_or_else(|e| e.into_inner()).get(&kind).cloned()
+}
+
+pub fn all() -> HashMap<ServiceKind, ServiceEndpoint> {
+  ENDPOINTS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn is_free(port: u16) -> bool {
+  TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+// Puerto libre asignado por el sistema
+pub fn free_port() -> Result<u16, String> {
+  let listener = TcpListener::bind(("127.0.0.1", 0)).map_err(|e| e.to_string())?;
+  listener.local_addr().map(|a| a.port()).map_err(|e| e.to_string())
+}
+
+pub fn pick(preferred: u16) -> Result<u16, String> {
+  if is_free(preferred) { Ok(preferred) } else { free_port() }
+}
+
+// Token por instalación con el que se lanza el servidor Node (GANADO_INSTANCE_TOKEN)
+pub fn instance_token(app: &tauri::AppHandle) -> String {
+  let path = crate::app_data_dir(app).map(|d| d.join("instance-token"));
+  if let Some(existing) = path.as_ref().and_then(|p| std::fs::read_to_string(p).ok()) {
+    let existing = existing.trim().to_string();
+    if existing.len() >= 32 { return existing; }
+  }
+  let token = hex::encode(rand::random::<[u8; 32]>());
+  if let Some(p) = path {
+    if let Some(dir) = p.parent() { let _ = std::fs::create_dir_all(dir); }
+    let _ = std::fs::write(p, &token);
+  }
+  token
+}
+
+fn probe_client() -> Result<reqwest::Client, String> {
+  reqwest::Client::builder().timeout(Duration::from_millis(1500)).build().map_err(|e| e.to_string())
+}
+
+// El servidor Next en `port` responde /api/instance con sha256(nonce:token)
+pub async fn verify_node(port: u16, token: &str) -> bool {
+  let Ok(client) = probe_client() else { return false };
+  let nonce = hex::encode(rand::random::<[u8; 16]>());
+  let url = format!("http://127.0.0.1:{}/api/instance?nonce={}", port, nonce);
+  let Ok(res) = client.get(&url).header("Cache-Control", "no-store").send().await else { return false };
+  if !res.status().is_success() { return false; }
+  let Ok(body) = res.json::<serde_json::Value>().await else { return false };
+  let expected = hex::encode(Sha256::digest(format!("{}:{}", nonce, token).as_bytes()));
+  body.get("app").and_then(|v| v.as_str()) == Some("ganado-ai")
+    && body.get("proof").and_then(|v| v.as_str()) == Some(expected.as_str())
+}
+
+// Lo que escucha en `port` es un servidor Ollama (responde /api/version)
+pub async fn is_ollama(port: u16) -> bool {
+  let Ok(client) = probe_client() else { return false };
+  let url = format!("http://127.0.0.1:{}/api/version", port);
+  let Ok(res) = client.get(&url).send().await else { return false };
+  if !res.status().is_success() { return false; }
+  res.json::<serde_json::Value>().await.ok()
+    .and_then(|v| v.get("version").and_then(|s| s.as_str()).map(|_| ()))
+    .is_some()
+}
+
+// El Ollama de `port` es el que lanzó esta instancia (con OLLAMA_MODELS en
+// ollama-store). Uno del sistema sobre ~/.ollama también responde /api/version,
+// pero adoptarlo haría que integridad y desalojo miren otro almacén.
+pub async fn is_own_ollama(port: u16) -> bool {
+  get(ServiceKind::Ollama).is_some_and(|e| e.port == port && e.owned) && is_ollama(port).await
+}
+
+// URLs de cada servicio para el frontend (en lugar de 127.0.0.1:4317 fijo)
+#[tauri::command]
+pub fn get_service_endpoints() -> HashMap<ServiceKind, ServiceEndpoint> {
+  all()
+}
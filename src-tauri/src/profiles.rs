@@ -0,0 +1,441 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::ollama::CreateRequest;
+
+// Perfiles de modelo: system prompt, PARAMETER, TEMPLATE y ADAPTER con los que
+// se crea cada tag de Ollama. Viven como JSON versionado en app_data/profiles
+// (las versiones anteriores quedan en profiles/history) y el tag se recrea
+// cuando cambia el perfil que le corresponde.
+
+const DEFAULT_PROFILE: &str = "ganadero";
+const DEEPSEEK_PROFILE: &str = "ganadero-deepseek";
+
+const SYSTEM_PROMPT: &str = "Eres Ganado AI, el asistente de una finca ganadera en Colombia. Responde siempre en español colombiano, claro y breve, con consejos prácticos para el trabajo diario.
+Conoces de identificación y trazabilidad de animales, reproducción (celos, servicios, preñeces, partos), sanidad (vacunación contra aftosa y brucelosis, desparasitación, tratamientos), producción de leche, nutrición y rotación de potreros, inventario y movimientos de ganado.
+Usa solo los datos de la finca que te den; si falta información, pídela. Ante signos de enfermedad grave, recomienda llamar al médico veterinario.";
+
+// Plantilla de chat de DeepSeek-R1 (la misma que publica Ollama para esa familia)
+const DEEPSEEK_TEMPLATE: &str = "{{- if .System }}{{ .System }}{{ end }}
+{{- range $i, $_ := .Messages }}
+{{- $last := eq (len (slice $.Messages $i)) 1}}
+{{- if eq .Role \"user\" }}<｜User｜>{{ .Content }}
+{{- else if eq .Role \"assistant\" }}<｜Assistant｜>{{ .Content }}{{- if not $last }}<｜end▁of▁sentence｜>{{- end }}
+{{- end }}
+{{- if and $last (ne .Role \"assistant\") }}<｜Assistant｜>{{- end }}
+{{- end }}";
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ModelProfile {
+  pub name: String,
+  pub version: u32,
+  // Tags a los que aplica; "*" al final es prefijo ("deepseek-r1*")
+  pub match_tags: Vec<String>,
+  pub system: Option<String>,
+  // Valores de PARAMETER; un arreglo genera una línea por elemento (stop)
+  pub parameters: BTreeMap<String, serde_json::Value>,
+  pub template: Option<String>,
+  // Rutas de adaptadores LoRA en GGUF
+  pub adapters: Vec<String>,
+  // Huella del perfil integrado del que se copió; si el contenido aún coincide,
+  // el usuario no lo editó y se puede reemplazar por una revisión más nueva
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub builtin_revision: Option<String>,
+}
+
+// Perfil con el que se creó cada tag
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AppliedProfile {
+  pub profile: String,
+  pub version: u32,
+  pub fingerprint: String,
+  pub gguf_sha256: String,
+  // Ruta del GGUF importado, para recrear el tag con el mismo archivo
+  pub gguf_path: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ProfileView {
+  pub profile: ModelProfile,
+  pub modelfile: String,
+}
+
+fn builtin_profiles() -> Vec<ModelProfile> {
+  let base_parameters = BTreeMap::from([
+    ("num_ctx".to_string(), serde_json::json!(4096)),
+    ("temperature".to_string(), serde_json::json!(0.6)),
+    ("top_p".to_string(), serde_json::json!(0.95)),
+  ]);
+  let mut deepseek_parameters = base_parameters.clone();
+  deepseek_parameters.insert("stop".into(), serde_json::json!(["<｜begin▁of▁sentence｜>", "<｜end▁of▁sentence｜>", "<｜User｜>", "<｜Assistant｜>"]));
+  vec![
+    ModelProfile {
+      name: DEFAULT_PROFILE.into(),
+      version: 1,
+      match_tags: vec![],
+      system: Some(SYSTEM_PROMPT.into()),
+      parameters: base_parameters,
+      template: None,
+      adapters: vec![],
+      builtin_revision: None,
+    },
+    ModelProfile {
+      name: DEEPSEEK_PROFILE.into(),
+      version: 1,
+      match_tags: vec!["deepseek-r1*".into()],
+      system: Some(SYSTEM_PROMPT.into()),
+      parameters: deepseek_parameters,
+      template: Some(DEEPSEEK_TEMPLATE.into()),
+      adapters: vec![],
+      builtin_revision: None,
+    },
+  ]
+}
+
+fn quote(text: &str) -> String {
+  // Modelfile admite bloques """...""" siempre que el texto no los contenga
+  format!("\"\"\"{}\"\"\"", text.replace("\"\"\"", "\\\"\\\"\\\""))
+}
+
+fn parameter_value(value: &serde_json::Value) -> String {
+  match value {
+    serde_json::Value::String(s) => format!("\"{}\"", s.replace('"', "\\\"")),
+    other => other.to_string(),
+  }
+}
+
+// Texto de un Modelfile; `from` y `adapters` van ya en su forma final (ruta
+// entre comillas o `@sha256:<digest>` de un blob subido)
+pub fn render_modelfile<'a>(from: &str, adapters: &[String], template: Option<&str>, system: Option<&str>, parameters: impl IntoIterator<Item = (&'a String, &'a serde_json::Value)>) -> String {
+  let mut out = format!("FROM {}\n", from);
+  for adapter in adapters {
+    out.push_str(&format!("ADAPTER {}\n", adapter));
+  }
+  if let Some(template) = template {
+    out.push_str(&format!("TEMPLATE {}\n", quote(template)));
+  }
+  if let Some(system) = system {
+    out.push_str(&format!("SYSTEM {}\n", quote(system)));
+  }
+  for (key, value) in parameters {
+    match value {
+      serde_json::Value::Array(items) => {
+        for item in items { out.push_str(&format!("PARAMETER {} {}\n", key, parameter_value(item))); }
+      }
+      other => out.push_str(&format!("PARAMETER {} {}\n", key, parameter_value(other))),
+    }
+  }
+  out
+}
+
+impl ModelProfile {
+  pub fn matches(&self, tag: &str) -> bool {
+    let base = tag.split(':').next().unwrap_or(tag);
+    self.match_tags.iter().any(|pattern| match pattern.strip_suffix('*') {
+      Some(prefix) => tag.starts_with(prefix),
+      None => pattern == tag || pattern == base,
+    })
+  }
+
+  // Modelfile equivalente, para inspección y para crear el tag con el CLI
+  pub fn modelfile(&self, from: &str) -> String {
+    let path = |p: &str| format!("\"{}\"", p.replace('"', "\\\""));
+    let adapters: Vec<String> = self.adapters.iter().map(|a| path(a)).collect();
+    render_modelfile(&path(from), &adapters, self.template.as_deref(), self.system.as_deref(), &self.parameters)
+  }
+
+  // Campos de /api/create; files y adapters los completa quien sube los blobs
+  pub fn create_request(&self, tag: &str) -> CreateRequest {
+    CreateRequest {
+      model: tag.to_string(),
+      system: self.system.clone(),
+      template: self.template.clone(),
+      parameters: if self.parameters.is_empty() { None } else { Some(self.parameters.clone().into_iter().collect()) },
+      ..Default::default()
+    }
+  }
+
+  // Huella del contenido que afecta al tag (todo salvo nombre, versión y match_tags)
+  pub fn fingerprint(&self) -> String {
+    let content = serde_json::json!({
+      "system": self.system,
+      "parameters": self.parameters,
+      "template": self.template,
+      "adapters": self.adapters,
+    });
+    hex::encode(Sha256::digest(content.to_string().as_bytes()))
+  }
+
+  pub fn adapter_paths(&self) -> Vec<PathBuf> {
+    self.adapters.iter().map(PathBuf::from).collect()
+  }
+}
+
+fn profiles_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  Ok(crate::app_data_dir(app).ok_or("app_data_dir not found")?.join("profiles"))
+}
+
+fn profile_path(dir: &Path, name: &str) -> PathBuf {
+  dir.join(format!("{}.json", name))
+}
+
+fn valid_name(name: &str) -> bool {
+  !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// Escribe `profile` como versión siguiente de `previous`, que queda en profiles/history
+fn write_version(dir: &Path, profile: &mut ModelProfile, previous: Option<ModelProfile>) -> Result<(), String> {
+  profile.version = previous.as_ref().map(|p| p.version + 1).unwrap_or(1);
+  if let Some(prev) = previous {
+    let history = dir.join("history");
+    std::fs::create_dir_all(&history).map_err(|e| e.to_string())?;
+    let text = serde_json::to_string_pretty(&prev).map_err(|e| e.to_string())?;
+    std::fs::write(history.join(format!("{}.v{}.json", prev.name, prev.version)), text).map_err(|e| e.to_string())?;
+  }
+  let text = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+  std::fs::write(profile_path(dir, &profile.name), text).map_err(|e| e.to_string())
+}
+
+// Copia en disco de un perfil integrado: se escribe si falta y se reemplaza por
+// la revisión actual si la copia es una revisión anterior sin editar. Las copias
+// sin builtin_revision (versiones previas de la app) solo se marcan si coinciden.
+fn sync_builtin(dir: &Path, mut builtin: ModelProfile) -> Result<(), String> {
+  let revision = builtin.fingerprint();
+  builtin.builtin_revision = Some(revision.clone());
+  let path = profile_path(dir, &builtin.name);
+  let Some(on_disk) = std::fs::read_to_string(&path).ok().and_then(|t| serde_json::from_str::<ModelProfile>(&t).ok()) else {
+    if path.exists() { return Ok(()); }
+    return write_version(dir, &mut builtin, None);
+  };
+  let unmodified = on_disk.builtin_revision.as_deref() == Some(on_disk.fingerprint().as_str());
+  if unmodified && on_disk.builtin_revision.as_deref() != Some(revision.as_str()) {
+    return write_version(dir, &mut builtin, Some(on_disk));
+  }
+  if on_disk.builtin_revision.is_none() && on_disk.fingerprint() == revision {
+    let marked = ModelProfile { builtin_revision: Some(revision), ..on_disk };
+    let text = serde_json::to_string_pretty(&marked).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| e.to_string())?;
+  }
+  Ok(())
+}
+
+// Perfiles en disco; los integrados se escriben la primera vez para que se puedan editar
+pub fn load_all(app: &tauri::AppHandle) -> Result<Vec<ModelProfile>, String> {
+  let dir = profiles_dir(app)?;
+  std::fs::create_dir_all(&dir).map_err(|e| format!("cannot create profiles dir: {}", e))?;
+  for builtin in builtin_profiles() {
+    sync_builtin(&dir, builtin)?;
+  }
+  let mut profiles = Vec::new();
+  for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())?.flatten() {
+    let path = entry.path();
+    if path.extension().and_then(|e| e.to_str()) != Some("json") || path.file_name().and_then(|n| n.to_str()) == Some("applied.json") { continue; }
+    match std::fs::read_to_string(&path).ok().and_then(|t| serde_json::from_str::<ModelProfile>(&t).ok()) {
+      Some(profile) if valid_name(&profile.name) => profiles.push(profile),
+      _ => {
+        let line = format!("[tauri] perfil inválido ignorado: {}", path.display());
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { crate::boot_log(&app, line).await });
+      }
+    }
+  }
+  profiles.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(profiles)
+}
+
+// Perfil que corresponde a un tag: el primero cuyo match_tags lo incluya o el por defecto
+pub fn for_tag(app: &tauri::AppHandle, tag: &str) -> Result<ModelProfile, String> {
+  let profiles = load_all(app)?;
+  if let Some(p) = profiles.iter().find(|p| p.name != DEFAULT_PROFILE && p.matches(tag)) {
+    return Ok(p.clone());
+  }
+  profiles.into_iter().find(|p| p.name == DEFAULT_PROFILE)
+    .ok_or_else(|| format!("profile '{}' not found", DEFAULT_PROFILE))
+}
+
+fn applied_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  Ok(profiles_dir(app)?.join("applied.json"))
+}
+
+// tag -> perfil y GGUF con los que se creó
+pub fn applied_all(app: &tauri::AppHandle) -> HashMap<String, AppliedProfile> {
+  applied_path(app).ok()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|b| serde_json::from_slice(&b).ok())
+    .unwrap_or_default()
+}
+
+pub fn applied(app: &tauri::AppHandle, tag: &str) -> Option<AppliedProfile> {
+  applied_all(app).remove(tag)
+}
+
+// Registra el perfil aplicado y deja el Modelfile equivalente en profiles/generated
+pub fn record_applied(app: &tauri::AppHandle, tag: &str, profile: &ModelProfile, gguf: &Path, gguf_sha256: &str) -> Result<(), String> {
+  let path = applied_path(app)?;
+  let mut map: HashMap<String, AppliedProfile> = std::fs::read(&path).ok().and_then(|b| serde_json::from_slice(&b).ok()).unwrap_or_default();
+  map.insert(tag.to_string(), AppliedProfile {
+    profile: profile.name.clone(),
+    version: profile.version,
+    fingerprint: profile.fingerprint(),
+    gguf_sha256: gguf_sha256.to_string(),
+    gguf_path: gguf.to_string_lossy().into_owned(),
+  });
+  std::fs::write(&path, serde_json::to_vec_pretty(&map).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+  let generated = profiles_dir(app)?.join("generated");
+  let _ = std::fs::create_dir_all(&generated);
+  let file = tag.replace([':', '/'], "_");
+  let _ = std::fs::write(generated.join(format!("{}.Modelfile", file)), profile.modelfile(&gguf.to_string_lossy()));
+  Ok(())
+}
+
+// Olvida los tags que cumplen `pred` (p. ej. al desinstalar su GGUF) junto con su Modelfile generado
+pub fn forget_applied(app: &tauri::AppHandle, pred: impl Fn(&str, &AppliedProfile) -> bool) -> Result<(), String> {
+  let path = applied_path(app)?;
+  let mut map = applied_all(app);
+  let forgotten: Vec<String> = map.iter().filter(|(tag, applied)| pred(tag, applied)).map(|(tag, _)| tag.clone()).collect();
+  if forgotten.is_empty() { return Ok(()); }
+  let generated = profiles_dir(app)?.join("generated");
+  for tag in &forgotten {
+    map.remove(tag);
+    let _ = std::fs::remove_file(generated.join(format!("{}.Modelfile", tag.replace([':', '/'], "_"))));
+  }
+  std::fs::write(&path, serde_json::to_vec_pretty(&map).map_err(|e| e.to_string())?).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_model_profiles(app: tauri::AppHandle) -> Result<Vec<ModelProfile>, String> {
+  load_all(&app)
+}
+
+// Perfil que usaría el tag y el Modelfile resultante
+#[tauri::command]
+pub fn get_model_profile(tag: String, model_path: Option<String>, app: tauri::AppHandle) -> Result<ProfileView, String> {
+  let profile = for_tag(&app, &tag)?;
+  let modelfile = profile.modelfile(model_path.as_deref().unwrap_or("<modelo.gguf>"));
+  Ok(ProfileView { profile, modelfile })
+}
+
+// Guarda una nueva versión del perfil; la anterior queda en profiles/history.
+// Los tags afectados se recrean en el próximo ensure_ollama_model_available.
+#[tauri::command]
+pub fn save_model_profile(mut profile: ModelProfile, app: tauri::AppHandle) -> Result<ModelProfile, String> {
+  if !valid_name(&profile.name) { return Err(format!("invalid profile name '{}'", profile.name)); }
+  for adapter in &profile.adapters {
+    if !Path::new(adapter).is_file() { return Err(format!("adapter not found: {}", adapter)); }
+  }
+  let dir = profiles_dir(&app)?;
+  std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+  let previous = std::fs::read_to_string(profile_path(&dir, &profile.name)).ok().and_then(|t| serde_json::from_str::<ModelProfile>(&t).ok());
+  write_version(&dir, &mut profile, previous)?;
+  Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn profile() -> ModelProfile {
+    ModelProfile {
+      name: "p".into(),
+      version: 1,
+      system: Some("hola".into()),
+      parameters: BTreeMap::from([
+        ("temperature".to_string(), serde_json::json!(0.6)),
+        ("stop".to_string(), serde_json::json!(["<a>", "say \"b\""])),
+      ]),
+      ..Default::default()
+    }
+  }
+
+  // Directorio vacío propio de cada prueba
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ganado-profiles-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn read(dir: &Path, name: &str) -> ModelProfile {
+    serde_json::from_str(&std::fs::read_to_string(profile_path(dir, name)).unwrap()).unwrap()
+  }
+
+  #[test]
+  fn fingerprint_ignores_metadata() {
+    let base = profile();
+    let renamed = ModelProfile { name: "otro".into(), version: 9, match_tags: vec!["x*".into()], builtin_revision: Some("r".into()), ..profile() };
+    assert_eq!(base.fingerprint(), renamed.fingerprint());
+    let edited = ModelProfile { system: Some("adiós".into()), ..profile() };
+    assert_ne!(base.fingerprint(), edited.fingerprint());
+  }
+
+  #[test]
+  fn modelfile_quotes_text_blocks_and_parameters() {
+    let p = ModelProfile { system: Some("uno \"\"\" dos".into()), template: Some("{{ .Prompt }}".into()), adapters: vec!["/a/lora.gguf".into()], ..profile() };
+    let text = p.modelfile("/m/model \"1\".gguf");
+    assert!(text.starts_with("FROM \"/m/model \\\"1\\\".gguf\"\n"));
+    assert!(text.contains("ADAPTER \"/a/lora.gguf\"\n"));
+    assert!(text.contains("TEMPLATE \"\"\"{{ .Prompt }}\"\"\"\n"));
+    // Un """ dentro del texto no cierra el bloque
+    assert!(text.contains("SYSTEM \"\"\"uno \\\"\\\"\\\" dos\"\"\"\n"));
+    assert!(text.contains("PARAMETER stop \"<a>\"\nPARAMETER stop \"say \\\"b\\\"\"\n"));
+    assert!(text.contains("PARAMETER temperature 0.6\n"));
+  }
+
+  #[test]
+  fn create_request_modelfile_uses_blob_digests() {
+    let mut req = profile().create_request("t:latest");
+    req.files = Some(HashMap::from([("m.gguf".to_string(), "sha256:abc".to_string())]));
+    req.system = Some("a \"\"\" b".into());
+    let text = req.modelfile();
+    assert!(text.starts_with("FROM @sha256:abc\n"));
+    assert!(text.contains("SYSTEM \"\"\"a \\\"\\\"\\\" b\"\"\"\n"));
+  }
+
+  #[test]
+  fn unmodified_builtin_is_upgraded() {
+    let dir = temp_dir("upgrade");
+    sync_builtin(&dir, profile()).unwrap();
+    assert_eq!(read(&dir, "p").version, 1);
+
+    let newer = ModelProfile { system: Some("hola, nueva revisión".into()), ..profile() };
+    sync_builtin(&dir, newer.clone()).unwrap();
+    let on_disk = read(&dir, "p");
+    assert_eq!(on_disk.version, 2);
+    assert_eq!(on_disk.system, newer.system);
+    assert!(dir.join("history").join("p.v1.json").exists());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn edited_builtin_is_kept() {
+    let dir = temp_dir("edited");
+    sync_builtin(&dir, profile()).unwrap();
+    let mut edited = read(&dir, "p");
+    edited.system = Some("mi prompt".into());
+    std::fs::write(profile_path(&dir, "p"), serde_json::to_string(&edited).unwrap()).unwrap();
+
+    sync_builtin(&dir, ModelProfile { system: Some("hola, nueva revisión".into()), ..profile() }).unwrap();
+    assert_eq!(read(&dir, "p").system.as_deref(), Some("mi prompt"));
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn legacy_copy_is_marked_only_when_unchanged() {
+    let dir = temp_dir("legacy");
+    std::fs::write(profile_path(&dir, "p"), serde_json::to_string(&profile()).unwrap()).unwrap();
+    sync_builtin(&dir, profile()).unwrap();
+    assert_eq!(read(&dir, "p").builtin_revision, Some(profile().fingerprint()));
+
+    let legacy_edit = ModelProfile { system: Some("mío".into()), ..profile() };
+    std::fs::write(profile_path(&dir, "p"), serde_json::to_string(&legacy_edit).unwrap()).unwrap();
+    sync_builtin(&dir, profile()).unwrap();
+    let on_disk = read(&dir, "p");
+    assert_eq!(on_disk.system.as_deref(), Some("mío"));
+    assert_eq!(on_disk.builtin_revision, None);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
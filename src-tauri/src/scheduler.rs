@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use tauri::Manager;
+use tokio::sync::{oneshot, Notify};
+
+use crate::chat::ChatBackend;
+use crate::settings;
+
+// Cola de inferencia por motor: pocas generaciones simultáneas (una por defecto,
+// en CPU dos chats a la vez van lentos o agotan la memoria de Ollama), el chat
+// interactivo pasa delante de los trabajos de fondo y cada espera tiene tope.
+// La posición en la cola sale como evento `ai-queue` con el requestId.
+
+const DEFAULT_MAX_IN_FLIGHT: usize = 1;
+const DEFAULT_MAX_QUEUE: usize = 8;
+const DEFAULT_QUEUE_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_GENERATION_TIMEOUT_SECS: u64 = 300;
+
+// Límites del planificador; cada campo en None usa el valor por defecto
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+  // Generaciones simultáneas por motor
+  pub max_in_flight: Option<usize>,
+  // Peticiones en espera por motor; más allá se rechazan
+  pub max_queue: Option<usize>,
+  // Tiempo máximo esperando turno
+  pub queue_timeout_secs: Option<u64>,
+  // Tiempo máximo de una generación una vez iniciada
+  pub generation_timeout_secs: Option<u64>,
+}
+
+impl SchedulerConfig {
+  pub fn max_in_flight(&self) -> usize { self.max_in_flight.unwrap_or(DEFAULT_MAX_IN_FLIGHT).max(1) }
+  pub fn max_queue(&self) -> usize { self.max_queue.unwrap_or(DEFAULT_MAX_QUEUE) }
+  pub fn queue_timeout(&self) -> Duration { Duration::from_secs(self.queue_timeout_secs.unwrap_or(DEFAULT_QUEUE_TIMEOUT_SECS)) }
+  pub fn generation_timeout(&self) -> Duration { Duration::from_secs(self.generation_timeout_secs.unwrap_or(DEFAULT_GENERATION_TIMEOUT_SECS)) }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+  // Chat que el usuario está mirando
+  #[default]
+  Interactive,
+  // Resúmenes, clasificación y demás trabajos sin nadie esperando
+  Background,
+}
+
+// Motivo por el que una petición no llegó a tener turno
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueueError {
+  Full { backend: ChatBackend, waiting: usize },
+  Timeout { backend: ChatBackend },
+  Cancelled,
+}
+
+impl std::fmt::Display for QueueError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      QueueError::Full { backend, waiting } => write!(f, "cola de inferencia de {} llena ({} en espera)", backend.name(), waiting),
+      QueueError::Timeout { backend } => write!(f, "tiempo de espera agotado en la cola de {}", backend.name()),
+      QueueError::Cancelled => write!(f, "cancelado mientras esperaba turno"),
+    }
+  }
+}
+
+impl From<QueueError> for String {
+  fn from(e: QueueError) -> String { e.to_string() }
+}
+
+struct Waiter {
+  request_id: String,
+  priority: Priority,
+  seq: u64,
+  enqueued: Instant,
+  // El turno viaja como Permit: si quien esperaba ya soltó el receptor, el
+  // Permit se descarta y su Drop devuelve el hueco
+  grant: oneshot::Sender<Permit>,
+}
+
+#[derive(Default)]
+struct BackendQueue {
+  in_flight: usize,
+  waiting: Vec<Waiter>,
+}
+
+impl BackendQueue {
+  // Orden de atención: prioridad y, a igualdad, orden de llegada
+  fn sort(&mut self) {
+    self.waiting.sort_by_key(|w| (w.priority, w.seq));
+  }
+
+  // Saca de la cola a los que caben y les reserva hueco
+  fn take_grants(&mut self, max_in_flight: usize) -> Vec<Waiter> {
+    let mut granted = Vec::new();
+    while self.in_flight < max_in_flight && !self.waiting.is_empty() {
+      granted.push(self.waiting.remove(0));
+      self.in_flight += 1;
+    }
+    granted
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueEvent {
+  pub request_id: String,
+  pub backend: ChatBackend,
+  pub state: &'static str,
+  pub position: Option<usize>,
+  pub queued: usize,
+}
+
+type EventSink = Box<dyn Fn(QueueEvent) + Send + Sync>;
+
+struct Scheduler {
+  queues: HashMap<ChatBackend, BackendQueue>,
+  next_seq: u64,
+  // Copia de settings.scheduler; set_scheduler_config la mantiene al día
+  config: SchedulerConfig,
+  // Destino de los eventos `ai-queue`; None hasta el primer uso con un AppHandle
+  events: Option<EventSink>,
+}
+
+impl Scheduler {
+  fn new(config: SchedulerConfig) -> Self {
+    Scheduler { queues: HashMap::new(), next_seq: 0, config, events: None }
+  }
+
+  // Da turno a los que esperan mientras haya hueco; se entregan tras soltar el lock
+  fn dispatch(&mut self, backend: ChatBackend) -> Grants {
+    let max_in_flight = self.config.max_in_flight();
+    let queue = self.queues.entry(backend).or_default();
+    let waiters = queue.take_grants(max_in_flight);
+    for w in &waiters {
+      emit(&self.events, &w.request_id, backend, "running", None, queue.waiting.len());
+    }
+    if !waiters.is_empty() { emit_positions(&self.events, backend, queue); }
+    Grants { backend, generation_timeout: self.config.generation_timeout(), waiters }
+  }
+}
+
+type Shared = &'static std::sync::Mutex<Scheduler>;
+
+static SCHEDULER: Lazy<std::sync::Mutex<Scheduler>> = Lazy::new(|| std::sync::Mutex::new(Scheduler::new(SchedulerConfig::default())));
+
+fn lock(scheduler: Shared) -> std::sync::MutexGuard<'static, Scheduler> {
+  scheduler.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Planificador global con la configuración de settings y los eventos hacia la UI
+fn shared(app: &tauri::AppHandle) -> Shared {
+  let scheduler: Shared = &SCHEDULER;
+  let mut guard = lock(scheduler);
+  if guard.events.is_none() {
+    guard.config = settings::load(app).scheduler;
+    let app = app.clone();
+    guard.events = Some(Box::new(move |event| { let _ = app.emit_all("ai-queue", event); }));
+  }
+  scheduler
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedRequest {
+  pub request_id: String,
+  pub priority: Priority,
+  pub position: usize,
+  pub waited_ms: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStatus {
+  pub backend: ChatBackend,
+  pub in_flight: usize,
+  pub max_in_flight: usize,
+  pub waiting: Vec<QueuedRequest>,
+}
+
+fn emit(events: &Option<EventSink>, request_id: &str, backend: ChatBackend, state: &'static str, position: Option<usize>, queued: usize) {
+  if let Some(events) = events {
+    events(QueueEvent { request_id: request_id.to_string(), backend, state, position, queued });
+  }
+}
+
+fn emit_positions(events: &Option<EventSink>, backend: ChatBackend, queue: &BackendQueue) {
+  for (i, w) in queue.waiting.iter().enumerate() {
+    emit(events, &w.request_id, backend, "queued", Some(i + 1), queue.waiting.len());
+  }
+}
+
+// Turnos reservados bajo el lock, pendientes de entregar
+struct Grants {
+  backend: ChatBackend,
+  generation_timeout: Duration,
+  waiters: Vec<Waiter>,
+}
+
+impl Grants {
+  fn deliver(self, scheduler: Shared) {
+    for w in self.waiters {
+      // Si quien esperaba ya se fue, send devuelve el Permit y al soltarlo el
+      // hueco pasa al siguiente
+      let _ = w.grant.send(Permit { scheduler, backend: self.backend, generation_timeout: self.generation_timeout });
+    }
+  }
+}
+
+// Turno concedido; al soltarlo pasa el siguiente de la cola
+pub struct Permit {
+  scheduler: Shared,
+  backend: ChatBackend,
+  pub generation_timeout: Duration,
+}
+
+impl Drop for Permit {
+  fn drop(&mut self) {
+    let grants = {
+      let mut scheduler = lock(self.scheduler);
+      let queue = scheduler.queues.entry(self.backend).or_default();
+      queue.in_flight = queue.in_flight.saturating_sub(1);
+      scheduler.dispatch(self.backend)
+    };
+    grants.deliver(self.scheduler);
+  }
+}
+
+// Espera turno en la cola de `backend`. Falla si la cola está llena, si se
+// agota queue_timeout o si `cancel` se dispara antes de empezar.
+pub async fn acquire(app: &tauri::AppHandle, backend: ChatBackend, request_id: &str, priority: Priority, cancel: Option<&Notify>) -> Result<Permit, QueueError> {
+  wait_turn(shared(app), backend, request_id, priority, cancel).await
+}
+
+async fn wait_turn(scheduler: Shared, backend: ChatBackend, request_id: &str, priority: Priority, cancel: Option<&Notify>) -> Result<Permit, QueueError> {
+  let (grant, mut granted) = oneshot::channel::<Permit>();
+  let queue_timeout = {
+    let mut guard = lock(scheduler);
+    guard.next_seq += 1;
+    let seq = guard.next_seq;
+    let config = guard.config.clone();
+    let Scheduler { queues, events, .. } = &mut *guard;
+    let queue = queues.entry(backend).or_default();
+    // Esperas abandonadas (futuro soltado sin cancelar) no ocupan cola
+    queue.waiting.retain(|w| !w.grant.is_closed());
+    if queue.in_flight < config.max_in_flight() && queue.waiting.iter().all(|w| w.priority > priority) {
+      queue.in_flight += 1;
+      emit(events, request_id, backend, "running", None, queue.waiting.len());
+      return Ok(Permit { scheduler, backend, generation_timeout: config.generation_timeout() });
+    }
+    if queue.waiting.len() >= config.max_queue() {
+      emit(events, request_id, backend, "rejected", None, queue.waiting.len());
+      return Err(QueueError::Full { backend, waiting: queue.waiting.len() });
+    }
+    queue.waiting.push(Waiter { request_id: request_id.to_string(), priority, seq, enqueued: Instant::now(), grant });
+    queue.sort();
+    emit_positions(events, backend, queue);
+    config.queue_timeout()
+  };
+
+  let outcome = tokio::select! {
+    r = tokio::time::timeout(queue_timeout, &mut granted) => match r {
+      Ok(Ok(permit)) => return Ok(permit),
+      Ok(Err(_)) => "cancelled",
+      Err(_) => "timeout",
+    },
+    _ = async { match cancel { Some(c) => c.notified().await, None => std::future::pending().await } } => "cancelled",
+  };
+  settle(scheduler, backend, request_id, outcome, granted)
+}
+
+// Sale de la cola tras un timeout o una cancelación. Si el turno llegó justo a
+// la vez, tras un timeout se aprovecha; si se canceló, el Permit se suelta y el
+// hueco pasa al siguiente. Un turno aún en camino vuelve solo al cerrar `granted`.
+fn settle(scheduler: Shared, backend: ChatBackend, request_id: &str, outcome: &'static str, mut granted: oneshot::Receiver<Permit>) -> Result<Permit, QueueError> {
+  let mut guard = lock(scheduler);
+  guard.queues.entry(backend).or_default().waiting.retain(|w| w.request_id != request_id);
+  if let Ok(permit) = granted.try_recv() {
+    if outcome == "timeout" { return Ok(permit); }
+    // Su Drop toma el lock para dar el hueco al siguiente
+    drop(guard);
+    drop(permit);
+    guard = lock(scheduler);
+  }
+  let Scheduler { queues, events, .. } = &mut *guard;
+  let queue = queues.entry(backend).or_default();
+  emit(events, request_id, backend, outcome, None, queue.waiting.len());
+  emit_positions(events, backend, queue);
+  Err(match outcome {
+    "timeout" => QueueError::Timeout { backend },
+    _ => QueueError::Cancelled,
+  })
+}
+
+pub fn status(app: &tauri::AppHandle) -> Vec<QueueStatus> {
+  let scheduler = lock(shared(app));
+  let max_in_flight = scheduler.config.max_in_flight();
+  [ChatBackend::Ollama, ChatBackend::Llama].into_iter().map(|backend| {
+    let queue = scheduler.queues.get(&backend);
+    QueueStatus {
+      backend,
+      in_flight: queue.map(|q| q.in_flight).unwrap_or(0),
+      max_in_flight,
+      waiting: queue.map(|q| q.waiting.iter().enumerate().map(|(i, w)| QueuedRequest {
+        request_id: w.request_id.clone(),
+        priority: w.priority,
+        position: i + 1,
+        waited_ms: w.enqueued.elapsed().as_millis() as u64,
+      }).collect()).unwrap_or_default(),
+    }
+  }).collect()
+}
+
+#[tauri::command]
+pub fn inference_queue_status(app: tauri::AppHandle) -> Vec<QueueStatus> {
+  status(&app)
+}
+
+#[tauri::command]
+pub fn get_scheduler_config(app: tauri::AppHandle) -> SchedulerConfig {
+  settings::load(&app).scheduler
+}
+
+#[tauri::command]
+pub fn set_scheduler_config(config: SchedulerConfig, app: tauri::AppHandle) -> Result<SchedulerConfig, String> {
+  if config.max_in_flight == Some(0) { return Err("max_in_flight must be at least 1".into()); }
+  if config.queue_timeout_secs == Some(0) || config.generation_timeout_secs == Some(0) {
+    return Err("timeouts must be greater than zero".into());
+  }
+  let saved = settings::update(&app, |s| s.scheduler = config)?;
+  // Con más huecos, los que esperaban pueden arrancar ya
+  let scheduler = shared(&app);
+  let grants: Vec<Grants> = {
+    let mut guard = lock(scheduler);
+    guard.config = saved.scheduler.clone();
+    [ChatBackend::Ollama, ChatBackend::Llama].into_iter().map(|backend| guard.dispatch(backend)).collect()
+  };
+  for g in grants { g.deliver(scheduler); }
+  Ok(saved.scheduler)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const BACKEND: ChatBackend = ChatBackend::Ollama;
+
+  fn scheduler(max_in_flight: usize, max_queue: usize, queue_timeout_secs: u64) -> Shared {
+    let config = SchedulerConfig {
+      max_in_flight: Some(max_in_flight),
+      max_queue: Some(max_queue),
+      queue_timeout_secs: Some(queue_timeout_secs),
+      generation_timeout_secs: None,
+    };
+    Box::leak(Box::new(std::sync::Mutex::new(Scheduler::new(config))))
+  }
+
+  fn waiting(scheduler: Shared) -> Vec<String> {
+    lock(scheduler).queues.get(&BACKEND).map(|q| q.waiting.iter().map(|w| w.request_id.clone()).collect()).unwrap_or_default()
+  }
+
+  fn in_flight(scheduler: Shared) -> usize {
+    lock(scheduler).queues.get(&BACKEND).map(|q| q.in_flight).unwrap_or(0)
+  }
+
+  // Lanza la espera en otra tarea y vuelve cuando ya está en la cola
+  async fn enqueue(scheduler: Shared, id: &'static str, priority: Priority) -> tokio::task::JoinHandle<Result<Permit, QueueError>> {
+    let handle = tokio::spawn(wait_turn(scheduler, BACKEND, id, priority, None));
+    while !waiting(scheduler).iter().any(|w| w == id) && !handle.is_finished() {
+      tokio::task::yield_now().await;
+    }
+    handle
+  }
+
+  // Waiter puesto a mano, para fijar el momento en que llega el turno
+  fn push_waiter(scheduler: Shared, id: &str) -> oneshot::Receiver<Permit> {
+    let (grant, granted) = oneshot::channel();
+    let mut guard = lock(scheduler);
+    guard.next_seq += 1;
+    let seq = guard.next_seq;
+    guard.queues.entry(BACKEND).or_default().waiting.push(Waiter {
+      request_id: id.to_string(), priority: Priority::Interactive, seq, enqueued: Instant::now(), grant,
+    });
+    granted
+  }
+
+  #[tokio::test]
+  async fn interactive_goes_before_background() {
+    let s = scheduler(1, 8, 60);
+    let running = wait_turn(s, BACKEND, "first", Priority::Background, None).await.unwrap();
+    let b1 = enqueue(s, "b1", Priority::Background).await;
+    let b2 = enqueue(s, "b2", Priority::Background).await;
+    let i1 = enqueue(s, "i1", Priority::Interactive).await;
+    assert_eq!(waiting(s), ["i1", "b1", "b2"]);
+
+    drop(running);
+    let permit = i1.await.unwrap().unwrap();
+    assert_eq!(waiting(s), ["b1", "b2"]);
+    assert_eq!(in_flight(s), 1);
+    drop(permit);
+    let permit = b1.await.unwrap().unwrap();
+    drop(permit);
+    let permit = b2.await.unwrap().unwrap();
+    drop(permit);
+    assert_eq!(in_flight(s), 0);
+  }
+
+  #[tokio::test]
+  async fn full_queue_is_rejected() {
+    let s = scheduler(1, 1, 60);
+    let _running = wait_turn(s, BACKEND, "first", Priority::Interactive, None).await.unwrap();
+    let _queued = enqueue(s, "second", Priority::Interactive).await;
+    let rejected = wait_turn(s, BACKEND, "third", Priority::Interactive, None).await;
+    assert!(matches!(rejected, Err(QueueError::Full { waiting: 1, .. })));
+    assert_eq!(waiting(s), ["second"]);
+  }
+
+  #[tokio::test]
+  async fn queue_timeout_leaves_the_queue() {
+    let s = scheduler(1, 8, 1);
+    let _running = wait_turn(s, BACKEND, "first", Priority::Interactive, None).await.unwrap();
+    let late = wait_turn(s, BACKEND, "late", Priority::Interactive, None).await;
+    assert!(matches!(late, Err(QueueError::Timeout { .. })));
+    assert!(waiting(s).is_empty());
+    assert_eq!(in_flight(s), 1);
+  }
+
+  #[tokio::test]
+  async fn grant_racing_timeout_is_kept() {
+    let s = scheduler(1, 8, 60);
+    let running = wait_turn(s, BACKEND, "first", Priority::Interactive, None).await.unwrap();
+    let granted = push_waiter(s, "late");
+    // El turno queda en el canal justo cuando vence la espera
+    drop(running);
+    let permit = settle(s, BACKEND, "late", "timeout", granted).unwrap();
+    assert_eq!(in_flight(s), 1);
+    drop(permit);
+    assert_eq!(in_flight(s), 0);
+  }
+
+  #[tokio::test]
+  async fn grant_racing_cancel_passes_to_next() {
+    let s = scheduler(1, 8, 60);
+    let running = wait_turn(s, BACKEND, "first", Priority::Interactive, None).await.unwrap();
+    let granted = push_waiter(s, "cancelled");
+    let next = enqueue(s, "next", Priority::Interactive).await;
+    drop(running);
+    assert!(matches!(settle(s, BACKEND, "cancelled", "cancelled", granted), Err(QueueError::Cancelled)));
+    let permit = next.await.unwrap().unwrap();
+    assert_eq!(in_flight(s), 1);
+    drop(permit);
+    assert_eq!(in_flight(s), 0);
+  }
+
+  #[tokio::test]
+  async fn cancel_notify_leaves_the_queue() {
+    let s = scheduler(1, 8, 60);
+    let _running = wait_turn(s, BACKEND, "first", Priority::Interactive, None).await.unwrap();
+    let cancel = std::sync::Arc::new(Notify::new());
+    let waiter = {
+      let cancel = cancel.clone();
+      tokio::spawn(async move { wait_turn(s, BACKEND, "second", Priority::Interactive, Some(&cancel)).await })
+    };
+    while waiting(s).is_empty() { tokio::task::yield_now().await; }
+    cancel.notify_one();
+    assert!(matches!(waiter.await.unwrap(), Err(QueueError::Cancelled)));
+    assert!(waiting(s).is_empty());
+    assert_eq!(in_flight(s), 1);
+  }
+
+  #[tokio::test]
+  async fn dropped_waiter_does_not_leak_a_slot() {
+    let s = scheduler(1, 1, 60);
+    let running = wait_turn(s, BACKEND, "first", Priority::Interactive, None).await.unwrap();
+    let gone = enqueue(s, "gone", Priority::Interactive).await;
+    gone.abort();
+    let _ = gone.await;
+    // La espera abandonada no cuenta para max_queue
+    let next = enqueue(s, "next", Priority::Interactive).await;
+    assert_eq!(waiting(s), ["next"]);
+    drop(running);
+    let permit = next.await.unwrap().unwrap();
+    drop(permit);
+    assert_eq!(in_flight(s), 0);
+
+    // Turno concedido a quien ya soltó la espera: vuelve al soltarse el Permit
+    let running = wait_turn(s, BACKEND, "first", Priority::Interactive, None).await.unwrap();
+    drop(push_waiter(s, "gone"));
+    drop(running);
+    assert_eq!(in_flight(s), 0);
+    assert!(waiting(s).is_empty());
+  }
+}
@@ -0,0 +1,499 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use tauri::Manager;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+
+// Registro de procesos hijos (Node standalone, Ollama, llama-server) con un
+// supervisor que detecta caídas con try_wait y los relanza con backoff. Un
+// servicio solo se reinicia después de haber estado listo (mark_ready): si
+// falla durante el arranque, el error lo reporta quien lo lanzó.
+// Cada proceso vivo deja un PID file en app_data/run para poder cerrar los
+// huérfanos de una ejecución anterior que terminó de golpe.
+
+const SUPERVISE_INTERVAL: Duration = Duration::from_secs(1);
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(30);
+// Más de MAX_CRASHES caídas dentro de CRASH_WINDOW se considera un bucle de fallos
+const MAX_CRASHES: usize = 5;
+const CRASH_WINDOW: Duration = Duration::from_secs(120);
+const TAIL_LINES: usize = 20;
+// Tiempo que se espera tras SIGTERM/CTRL_BREAK antes de matar el proceso
+const STOP_GRACE: Duration = Duration::from_secs(5);
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceKind {
+  Node,
+  Ollama,
+  Llama,
+  // Gateway OpenAI en proceso; solo tiene endpoint, no proceso hijo
+  Gateway,
+}
+
+impl ServiceKind {
+  // Servicios que corren como proceso hijo supervisado
+  pub const ALL: [ServiceKind; 3] = [ServiceKind::Node, ServiceKind::Ollama, ServiceKind::Llama];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      ServiceKind::Node => "node",
+      ServiceKind::Ollama => "ollama",
+      ServiceKind::Llama => "llama",
+      ServiceKind::Gateway => "gateway",
+    }
+  }
+}
+
+// Todo lo necesario para volver a lanzar el proceso igual que la primera vez
+#[derive(Clone, Debug)]
+pub struct SpawnSpec {
+  pub program: String,
+  pub args: Vec<String>,
+  pub envs: Vec<(String, String)>,
+  pub current_dir: Option<PathBuf>,
+  pub log_path: PathBuf,
+  // Si está, cada línea de salida se reenvía también al boot log con este prefijo
+  pub boot_log_prefix: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServiceState {
+  Starting,
+  Running,
+  Restarting,
+  Exited,
+  Stopped,
+  CrashLoop,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceStatus {
+  pub name: ServiceKind,
+  pub state: ServiceState,
+  pub pid: Option<u32>,
+  pub uptime_secs: Option<u64>,
+  pub restarts: u32,
+  pub last_exit_code: Option<i32>,
+  pub last_error: Option<String>,
+  pub command: String,
+}
+
+struct ServiceEntry {
+  spec: SpawnSpec,
+  child: Option<Child>,
+  state: ServiceState,
+  started_at: Option<Instant>,
+  restarts: u32,
+  crashes: VecDeque<Instant>,
+  restart_at: Option<Instant>,
+  last_exit_code: Option<i32>,
+  last_error: Option<String>,
+  tail: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl ServiceEntry {
+  fn status(&self, kind: ServiceKind) -> ServiceStatus {
+    ServiceStatus {
+      name: kind,
+      state: self.state,
+      pid: self.child.as_ref().map(|c| c.id()),
+      uptime_secs: self.started_at.filter(|_| self.child.is_some()).map(|t| t.elapsed().as_secs()),
+      restarts: self.restarts,
+      last_exit_code: self.last_exit_code,
+      last_error: self.last_error.clone(),
+      command: self.spec.program.clone(),
+    }
+  }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PidFile {
+  pid: u32,
+  program: String,
+  // Segundos desde epoch; junto al nombre evita confundir un PID reutilizado
+  started_at: u64,
+}
+
+static SERVICES: Lazy<tauri::async_runtime::Mutex<HashMap<ServiceKind, ServiceEntry>>> = Lazy::new(|| tauri::async_runtime::Mutex::new(HashMap::new()));
+
+fn emit_status(app: &tauri::AppHandle, status: &ServiceStatus) {
+  let _ = app.emit_all("service-status", status);
+}
+
+fn open_log(path: &PathBuf) -> Result<File, String> {
+  if let Some(dir) = path.parent() { let _ = std::fs::create_dir_all(dir); }
+  OpenOptions::new().create(true).append(true).open(path).map_err(|e| format!("cannot open {}: {}", path.display(), e))
+}
+
+// Lee stdout/stderr línea a línea en hilos y los vuelca en el log del servicio,
+// en el boot log y en la cola de últimas líneas
+fn capture_output(app: &tauri::AppHandle, child: &mut Child, spec: &SpawnSpec, tail: Arc<Mutex<VecDeque<String>>>) {
+  let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+  if let Some(out) = child.stdout.take() {
+    let tx = tx.clone();
+    std::thread::spawn(move || {
+      for line in BufReader::new(out).lines().map_while(Result::ok) { let _ = tx.send(line); }
+    });
+  }
+  if let Some(err) = child.stderr.take() {
+    let tx = tx.clone();
+    std::thread::spawn(move || {
+      for line in BufReader::new(err).lines().map_while(Result::ok) { let _ = tx.send(line); }
+    });
+  }
+  drop(tx);
+  let mut logf = open_log(&spec.log_path).ok();
+  let prefix = spec.boot_log_prefix.clone().unwrap_or_default();
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    while let Some(line) = rx.recv().await {
+      if let Some(f) = logf.as_mut() { let _ = writeln!(f, "{}", line); }
+      if line.trim().is_empty() { continue; }
+      {
+        let mut t = tail.lock().unwrap_or_else(|e| e.into_inner());
+        t.push_back(line.clone());
+        if t.len() > TAIL_LINES { t.pop_front(); }
+      }
+      crate::boot_log(&app, format!("{} {}", prefix, line)).await;
+    }
+  });
+}
+
+fn pid_file_path(app: &tauri::AppHandle, kind: ServiceKind) -> Option<PathBuf> {
+  crate::app_data_dir(app).map(|d| d.join("run").join(format!("{}.pid", kind.name())))
+}
+
+fn now_secs() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn write_pid_file(app: &tauri::AppHandle, kind: ServiceKind, child: &Child, spec: &SpawnSpec) {
+  let Some(path) = pid_file_path(app, kind) else { return };
+  if let Some(dir) = path.parent() { let _ = std::fs::create_dir_all(dir); }
+  let record = PidFile { pid: child.id(), program: spec.program.clone(), started_at: now_secs() };
+  if let Ok(text) = serde_json::to_string(&record) { let _ = std::fs::write(&path, text); }
+}
+
+fn remove_pid_file(app: &tauri::AppHandle, kind: ServiceKind) {
+  if let Some(path) = pid_file_path(app, kind) { let _ = std::fs::remove_file(path); }
+}
+
+fn spawn(app: &tauri::AppHandle, kind: ServiceKind, spec: &SpawnSpec, tail: Arc<Mutex<VecDeque<String>>>) -> Result<Child, String> {
+  let mut cmd = Command::new(&spec.program);
+  cmd.args(&spec.args).envs(spec.envs.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+  if let Some(dir) = &spec.current_dir { cmd.current_dir(dir); }
+  // Grupo de procesos propio para poder mandarle CTRL_BREAK sin afectar a la app
+  #[cfg(windows)]
+  {
+    use std::os::windows::process::CommandExt;
+    cmd.creation_flags(windows_sys::Win32::System::Threading::CREATE_NEW_PROCESS_GROUP);
+  }
+  if spec.boot_log_prefix.is_some() {
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+  } else {
+    cmd.stdout(Stdio::from(open_log(&spec.log_path)?)).stderr(Stdio::from(open_log(&spec.log_path)?));
+  }
+  let mut child = cmd.spawn().map_err(|e| format!("cannot start '{}': {}", spec.program, e))?;
+  if spec.boot_log_prefix.is_some() { capture_output(app, &mut child, spec, tail); }
+  write_pid_file(app, kind, &child, spec);
+  Ok(child)
+}
+
+// Pide al proceso que termine (SIGTERM / CTRL_BREAK). Devuelve false si la
+// señal no se pudo entregar, p. ej. en Windows sin consola compartida.
+fn request_stop(pid: u32) -> bool {
+  #[cfg(unix)]
+  {
+    unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) == 0 }
+  }
+  #[cfg(windows)]
+  {
+    unsafe { windows_sys::Win32::System::Console::GenerateConsoleCtrlEvent(windows_sys::Win32::System::Console::CTRL_BREAK_EVENT, pid) != 0 }
+  }
+  #[cfg(not(any(unix, windows)))]
+  {
+    let _ = pid;
+    false
+  }
+}
+
+// Cierre ordenado de varios hijos a la vez: señal a todos, espera acotada
+// común, kill a los que sigan vivos y wait para no dejar zombis. Todo bloquea,
+// así que corre en spawn_blocking y sin tener tomado SERVICES.
+fn stop_children(mut children: Vec<Child>) {
+  let signalled: Vec<bool> = children.iter().map(|c| request_stop(c.id())).collect();
+  let deadline = Instant::now() + STOP_GRACE;
+  if signalled.iter().any(|s| *s) {
+    while Instant::now() < deadline {
+      let pending = children.iter_mut().zip(&signalled)
+        .any(|(c, s)| *s && matches!(c.try_wait(), Ok(None)));
+      if !pending { break; }
+      std::thread::sleep(STOP_POLL);
+    }
+  }
+  for child in children.iter_mut() {
+    if let Ok(None) = child.try_wait() { let _ = child.kill(); }
+    let _ = child.wait();
+  }
+}
+
+async fn terminate_all(children: Vec<Child>) {
+  if children.is_empty() { return; }
+  let _ = tauri::async_runtime::spawn_blocking(move || stop_children(children)).await;
+}
+
+async fn terminate(child: Child) {
+  terminate_all(vec![child]).await;
+}
+
+fn backoff(crashes: usize) -> Duration {
+  RESTART_BASE_DELAY.saturating_mul(1u32 << crashes.saturating_sub(1).min(8)).min(RESTART_MAX_DELAY)
+}
+
+// Lanza (o relanza) el servicio en estado Starting y devuelve su PID
+pub async fn launch(app: &tauri::AppHandle, kind: ServiceKind, spec: SpawnSpec) -> Result<u32, String> {
+  // La instancia anterior se cierra fuera del lock: mientras tanto el resto de
+  // servicios y las consultas de estado siguen respondiendo
+  let old = SERVICES.lock().await.remove(&kind);
+  if let Some(mut old) = old {
+    if let Some(child) = old.child.take() { terminate(child).await; }
+    remove_pid_file(app, kind);
+  }
+  let tail = Arc::new(Mutex::new(VecDeque::new()));
+  let child = spawn(app, kind, &spec, tail.clone())?;
+  let pid = child.id();
+  let entry = ServiceEntry {
+    spec,
+    child: Some(child),
+    state: ServiceState::Starting,
+    started_at: Some(Instant::now()),
+    restarts: 0,
+    crashes: VecDeque::new(),
+    restart_at: None,
+    last_exit_code: None,
+    last_error: None,
+    tail,
+  };
+  emit_status(app, &entry.status(kind));
+  // Otro launch del mismo servicio pudo colarse mientras se cerraba el anterior
+  let replaced = SERVICES.lock().await.insert(kind, entry);
+  if let Some(child) = replaced.and_then(|mut e| e.child.take()) { terminate(child).await; }
+  Ok(pid)
+}
+
+// El servicio respondió a su chequeo de salud: a partir de aquí se supervisa
+pub async fn mark_ready(app: &tauri::AppHandle, kind: ServiceKind) {
+  let mut services = SERVICES.lock().await;
+  if let Some(entry) = services.get_mut(&kind) {
+    if entry.state == ServiceState::Starting && entry.child.is_some() {
+      entry.state = ServiceState::Running;
+      emit_status(app, &entry.status(kind));
+    }
+  }
+}
+
+pub async fn stop(app: &tauri::AppHandle, kind: ServiceKind) {
+  let child = {
+    let mut services = SERVICES.lock().await;
+    let Some(entry) = services.get_mut(&kind) else { return };
+    let child = entry.child.take();
+    entry.state = ServiceState::Stopped;
+    entry.restart_at = None;
+    emit_status(app, &entry.status(kind));
+    child
+  };
+  if let Some(child) = child { terminate(child).await; }
+  remove_pid_file(app, kind);
+  crate::ports::forget(app, kind);
+}
+
+// Si el proceso ya terminó devuelve la descripción de su salida
+pub async fn exit_status(app: &tauri::AppHandle, kind: ServiceKind) -> Option<String> {
+  let mut services = SERVICES.lock().await;
+  let entry = services.get_mut(&kind)?;
+  if let Some(child) = entry.child.as_mut() {
+    match child.try_wait() {
+      Ok(Some(status)) => {
+        entry.child = None;
+        remove_pid_file(app, kind);
+        entry.last_exit_code = status.code();
+        entry.state = ServiceState::Exited;
+        return Some(status.to_string());
+      }
+      _ => return None,
+    }
+  }
+  Some(entry.last_exit_code.map(|c| format!("exit status: {}", c)).unwrap_or_else(|| "not running".into()))
+}
+
+// Últimas líneas de salida capturadas (solo servicios con boot_log_prefix)
+pub async fn tail(kind: ServiceKind) -> String {
+  let services = SERVICES.lock().await;
+  services.get(&kind)
+    .map(|e| e.tail.lock().unwrap_or_else(|p| p.into_inner()).iter().cloned().collect::<Vec<_>>().join("\n"))
+    .unwrap_or_default()
+}
+
+pub async fn statuses() -> Vec<ServiceStatus> {
+  let services = SERVICES.lock().await;
+  ServiceKind::ALL.iter().filter_map(|k| services.get(k).map(|e| e.status(*k))).collect()
+}
+
+// Una pasada del supervisor: recoge salidas, programa y ejecuta reinicios.
+// Devuelve los estados que cambiaron junto con la línea para el boot log.
+async fn supervise_once(app: &tauri::AppHandle) -> Vec<(ServiceStatus, String)> {
+  let mut services = SERVICES.lock().await;
+  let now = Instant::now();
+  let mut changed = Vec::new();
+  for (kind, entry) in services.iter_mut() {
+    let exited = entry.child.as_mut().and_then(|c| c.try_wait().ok().flatten());
+    if let Some(status) = exited {
+      entry.child = None;
+      remove_pid_file(app, *kind);
+      entry.last_exit_code = status.code();
+      let message = if entry.state == ServiceState::Running {
+        entry.crashes.push_back(now);
+        while entry.crashes.front().map(|t| now.duration_since(*t) > CRASH_WINDOW).unwrap_or(false) { entry.crashes.pop_front(); }
+        if entry.crashes.len() > MAX_CRASHES {
+          entry.state = ServiceState::CrashLoop;
+          crate::ports::forget(app, *kind);
+          format!("[tauri] {} terminó ({}); {} caídas en {}s, no se reinicia", kind.name(), status, entry.crashes.len(), CRASH_WINDOW.as_secs())
+        } else {
+          let delay = backoff(entry.crashes.len());
+          entry.state = ServiceState::Restarting;
+          entry.restart_at = Some(now + delay);
+          format!("[tauri] {} terminó ({}); reinicio en {}s", kind.name(), status, delay.as_secs())
+        }
+      } else {
+        entry.state = ServiceState::Exited;
+        format!("[tauri] {} terminó durante el arranque ({})", kind.name(), status)
+      };
+      changed.push((entry.status(*kind), message));
+    }
+
+    if entry.state == ServiceState::Restarting && entry.restart_at.map(|t| now >= t).unwrap_or(false) {
+      entry.restart_at = None;
+      entry.restarts += 1;
+      let message = match spawn(app, *kind, &entry.spec, entry.tail.clone()) {
+        Ok(child) => {
+          entry.child = Some(child);
+          entry.started_at = Some(now);
+          entry.state = ServiceState::Running;
+          format!("[tauri] {} reiniciado (reinicio #{})", kind.name(), entry.restarts)
+        }
+        Err(e) => {
+          entry.last_error = Some(e.clone());
+          entry.crashes.push_back(now);
+          if entry.crashes.len() > MAX_CRASHES {
+            entry.state = ServiceState::CrashLoop;
+            crate::ports::forget(app, *kind);
+          } else {
+            entry.restart_at = Some(now + backoff(entry.crashes.len()));
+          }
+          format!("[tauri] no se pudo reiniciar {}: {}", kind.name(), e)
+        }
+      };
+      changed.push((entry.status(*kind), message));
+    }
+  }
+  changed
+}
+
+// Al salir de la app: cierre ordenado del gateway y de todos los servicios en paralelo
+pub async fn shutdown_all(app: &tauri::AppHandle) {
+  // Primero el gateway, para que no reenvíe peticiones a motores que se están cerrando
+  crate::gateway::shutdown(app).await;
+  let mut children: Vec<Child> = Vec::new();
+  let kinds: Vec<ServiceKind> = {
+    let mut services = SERVICES.lock().await;
+    for (kind, entry) in services.iter_mut() {
+      entry.restart_at = None;
+      if let Some(child) = entry.child.take() {
+        children.push(child);
+        entry.state = ServiceState::Stopped;
+        emit_status(app, &entry.status(*kind));
+      }
+    }
+    services.keys().copied().collect()
+  };
+  if children.is_empty() { return; }
+  terminate_all(children).await;
+  for kind in kinds { remove_pid_file(app, kind); }
+}
+
+// Procesos que dejó una ejecución anterior que terminó sin cerrar a sus hijos
+// (crash, kill -9). Solo se tocan si el PID sigue siendo el mismo programa
+// arrancado a la misma hora; si no, el PID file simplemente se descarta.
+// Los PID files se leen y se señalan aquí, antes de lanzar servicios nuevos
+// (que escribirán los suyos); la espera y el kill siguen en segundo plano para
+// no retrasar la ventana.
+pub fn reap_orphans(app: &tauri::AppHandle) {
+  let mut sys = sysinfo::System::new();
+  let mut orphans: Vec<(ServiceKind, u32, bool)> = Vec::new();
+  for kind in ServiceKind::ALL {
+    let Some(path) = pid_file_path(app, kind) else { continue };
+    let Some(record) = std::fs::read_to_string(&path).ok().and_then(|t| serde_json::from_str::<PidFile>(&t).ok()) else {
+      let _ = std::fs::remove_file(&path);
+      continue;
+    };
+    let _ = std::fs::remove_file(&path);
+    let pid = sysinfo::Pid::from_u32(record.pid);
+    if !sys.refresh_process(pid) { continue; }
+    let Some(process) = sys.process(pid) else { continue };
+    if !is_same_process(process, &record) { continue; }
+    orphans.push((kind, record.pid, request_stop(record.pid)));
+  }
+  if orphans.is_empty() { return; }
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    let reaped = tauri::async_runtime::spawn_blocking(move || {
+      let deadline = Instant::now() + STOP_GRACE;
+      orphans.into_iter().map(|(kind, pid, delivered)| {
+        let pid = sysinfo::Pid::from_u32(pid);
+        while delivered && Instant::now() < deadline && sys.refresh_process(pid) {
+          std::thread::sleep(STOP_POLL);
+        }
+        if sys.refresh_process(pid) {
+          if let Some(process) = sys.process(pid) { process.kill(); }
+        }
+        format!("{} (pid {})", kind.name(), pid)
+      }).collect::<Vec<_>>()
+    }).await.unwrap_or_default();
+    crate::boot_log(&app, format!("[tauri] procesos huérfanos cerrados: {}", reaped.join(", "))).await;
+  });
+}
+
+fn is_same_process(process: &sysinfo::Process, record: &PidFile) -> bool {
+  // En Linux el nombre del proceso se trunca a 15 caracteres
+  let running = Path::new(process.name()).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+  let expected = Path::new(&record.program).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+  !running.is_empty() && expected.starts_with(&running) && process.start_time().abs_diff(record.started_at) <= 5
+}
+
+pub fn spawn_supervisor(app: tauri::AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      sleep(SUPERVISE_INTERVAL).await;
+      for (status, message) in supervise_once(&app).await {
+        emit_status(&app, &status);
+        crate::boot_log(&app, message).await;
+      }
+    }
+  });
+}
+
+#[tauri::command]
+pub async fn get_services_status() -> Vec<ServiceStatus> {
+  statuses().await
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use once_cell::sync::Lazy;
+
+// Preferencias locales de la app persistidas en app_data/settings.json
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+  // Ruta del GGUF fijado por el usuario; tiene prioridad sobre la selección automática
+  pub pinned_model: Option<String>,
+  // Tag de Ollama que se prepara al arrancar y usa el asistente; None usa models::DEFAULT_MODEL_TAG
+  pub default_model: Option<String>,
+  // Compartir los modelos instalados con otros equipos de la LAN (opt-in)
+  pub lan_sharing: bool,
+  // Puerto del servicio LAN; None usa el puerto por defecto
+  pub lan_port: Option<u16>,
+  // Tope en bytes para modelos, ollama-store, descargas, logs y binarios
+  pub storage_quota: Option<u64>,
+  // Parámetros de lanzamiento de llama-server
+  pub llama: crate::llama_config::LlamaServerConfig,
+  // Límites de la cola de inferencia (concurrencia, espera, timeouts)
+  pub scheduler: crate::scheduler::SchedulerConfig,
+}
+
+// Serializa lecturas-modificación-escritura para no perder cambios concurrentes
+static SETTINGS_LOCK: Lazy<std::sync::Mutex<()>> = Lazy::new(|| std::sync::Mutex::new(()));
+
+fn settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  let dir = crate::app_data_dir(app).ok_or("app_data_dir not found")?;
+  Ok(dir.join("settings.json"))
+}
+
+// Un settings.json que no se puede leer se aparta como settings.json.bad-<ts>
+// antes de seguir con los valores por defecto: el siguiente update no debe
+// pisar el modelo fijado, la cuota ni el resto de preferencias del usuario. Si
+// ni siquiera se puede apartar, update se niega a escribir.
+fn read(app: &tauri::AppHandle) -> Result<AppSettings, String> {
+  let Ok(path) = settings_path(app) else { return Ok(AppSettings::default()) };
+  let Ok(bytes) = std::fs::read(&path) else { return Ok(AppSettings::default()) };
+  match serde_json::from_slice(&bytes) {
+    Ok(settings) => Ok(settings),
+    Err(e) => {
+      let ts = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+      let backup = path.with_extension(format!("json.bad-{}", ts));
+      let (line, result) = match std::fs::rename(&path, &backup) {
+        Ok(()) => (format!("[tauri] settings.json inválido ({}), copia en {}; se usan valores por defecto", e, backup.display()), Ok(AppSettings::default())),
+        Err(re) => {
+          let line = format!("[tauri] settings.json inválido ({}) y no se pudo apartar: {}", e, re);
+          (line.clone(), Err(line))
+        }
+      };
+      let app = app.clone();
+      tauri::async_runtime::spawn(async move { crate::boot_log(&app, line).await });
+      result
+    }
+  }
+}
+
+pub fn load(app: &tauri::AppHandle) -> AppSettings {
+  let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  read(app).unwrap_or_default()
+}
+
+// Aplica `f` sobre la configuración actual y la guarda (tmp + rename)
+pub fn update<F: FnOnce(&mut AppSettings)>(app: &tauri::AppHandle, f: F) -> Result<AppSettings, String> {
+  let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut settings = read(app)?;
+  f(&mut settings);
+  let path = settings_path(app)?;
+  if let Some(parent) = path.parent() { std::fs::create_dir_all(parent).map_err(|e| e.to_string())?; }
+  let tmp = path.with_extension("json.tmp");
+  let bytes = serde_json::to_vec_pretty(&settings).map_err(|e| e.to_string())?;
+  std::fs::write(&tmp, bytes).map_err(|e| e.to_string())?;
+  std::fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+  Ok(settings)
+}
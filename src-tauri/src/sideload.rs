@@ -0,0 +1,192 @@
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::downloads;
+use crate::gguf;
+use crate::storage;
+
+// Emite progreso como mucho cada 8 MB para no saturar el canal de eventos
+const PROGRESS_STEP: u64 = 8 * 1024 * 1024;
+
+// Copia `src` en `dst` calculando el sha256 al vuelo
+pub fn copy_with_hash<F: FnMut(u64, u64)>(src: &Path, dst: &Path, mut on_progress: F) -> Result<String, String> {
+  let mut input = File::open(src).map_err(|e| format!("cannot open {}: {}", src.display(), e))?;
+  let total = input.metadata().map_err(|e| e.to_string())?.len();
+  let mut output = BufWriter::new(File::create(dst).map_err(|e| format!("cannot create {}: {}", dst.display(), e))?);
+  let mut hasher = Sha256::new();
+  let mut buf = vec![0u8; 1024 * 1024];
+  let mut copied: u64 = 0;
+  let mut last_emit: u64 = 0;
+  on_progress(0, total);
+  loop {
+    let n = input.read(&mut buf).map_err(|e| format!("read error on {}: {}", src.display(), e))?;
+    if n == 0 { break; }
+    output.write_all(&buf[..n]).map_err(|e| format!("write error on {}: {}", dst.display(), e))?;
+    hasher.update(&buf[..n]);
+    copied += n as u64;
+    if copied - last_emit >= PROGRESS_STEP {
+      on_progress(copied, total);
+      last_emit = copied;
+    }
+  }
+  output.flush().map_err(|e| e.to_string())?;
+  output.get_ref().sync_all().map_err(|e| e.to_string())?;
+  on_progress(copied, total);
+  if copied != total { return Err(format!("source changed while copying ({} of {} bytes)", copied, total)); }
+  Ok(hex::encode(hasher.finalize()))
+}
+
+// Instala un GGUF empaquetado en Resources/models: se copia a downloads/*.part,
+// se comprueba (sidecar .sha256 si lo trae y cabecera GGUF completa) y solo
+// entonces pasa a models/, así un corte a mitad nunca deja un .gguf truncado ahí
+pub fn install_bundled(app: &tauri::AppHandle, src: &Path) -> Result<PathBuf, String> {
+  let data_dir = crate::app_data_dir(app).ok_or("app_data_dir not found")?;
+  let models_dir = data_dir.join("models");
+  let partial_dir = data_dir.join("downloads");
+  std::fs::create_dir_all(&models_dir).map_err(|e| format!("cannot create models dir: {}", e))?;
+  std::fs::create_dir_all(&partial_dir).map_err(|e| format!("cannot create downloads dir: {}", e))?;
+  let file_name = src.file_name().ok_or("invalid model path")?.to_string_lossy().into_owned();
+  let target = models_dir.join(&file_name);
+  let part = partial_dir.join(format!("{}.bundled.part", file_name));
+  storage::preflight(app, &partial_dir, std::fs::metadata(src).map_err(|e| e.to_string())?.len())?;
+  let installed = copy_with_hash(src, &part, |_, _| {}).and_then(|digest| {
+    if let Some(expected) = downloads::read_digest_sidecar(src) {
+      if expected != digest { return Err(format!("sha256 mismatch for {}: got {}, expected {}", src.display(), digest, expected)); }
+    }
+    gguf::inspect(&part)?;
+    downloads::install_part(&part, &target, &digest)
+  });
+  if installed.is_err() { let _ = std::fs::remove_file(&part); }
+  installed.map(|()| target)
+}
+
+// Tag de Ollama derivado del nombre del archivo: "Mi-Modelo.Q4.gguf" -> "mi-modelo.q4:latest"
+fn tag_from_file_name(path: &Path) -> String {
+  let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("modelo");
+  let clean: String = stem.to_lowercase().chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-' { c } else { '-' })
+    .collect();
+  format!("{}:latest", clean.trim_matches('-'))
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ImportedModel {
+  pub path: String,
+  pub sha256: String,
+  pub tag: String,
+  pub catalog_id: Option<String>,
+}
+
+#[tauri::command]
+pub async fn import_model_from_path(path: String, sha256: Option<String>, tag: Option<String>, app: tauri::AppHandle, window: tauri::Window) -> Result<ImportedModel, String> {
+  let src = PathBuf::from(&path);
+  if !src.is_file() { return Err(format!("model file not found: {}", path)); }
+  // Rechazar de entrada lo que no sea un GGUF completo (USB mal expulsada, etc.)
+  let check = src.clone();
+  tauri::async_runtime::spawn_blocking(move || gguf::inspect(&check)).await.map_err(|e| e.to_string())??;
+
+  let data_dir = crate::app_data_dir(&app).ok_or("app_data_dir not found")?;
+  let models_dir = data_dir.join("models");
+  let partial_dir = data_dir.join("downloads");
+  std::fs::create_dir_all(&models_dir).map_err(|e| format!("cannot create models dir: {}", e))?;
+  std::fs::create_dir_all(&partial_dir).map_err(|e| format!("cannot create downloads dir: {}", e))?;
+  let file_name = src.file_name().ok_or("invalid model path")?.to_string_lossy().into_owned();
+  let part = partial_dir.join(format!("{}.import.part", file_name));
+  let size = std::fs::metadata(&src).map_err(|e| e.to_string())?.len();
+  storage::preflight(&app, &partial_dir, size)?;
+
+  // Hash esperado: argumento explícito o el sidecar .sha256 que deja export_model_to_path
+  let expected = sha256.map(|s| s.to_lowercase()).or_else(|| downloads::read_digest_sidecar(&src));
+
+  crate::boot_log(&app, format!("[tauri] importando modelo desde {}", src.display())).await;
+  let (copy_src, copy_dst, w) = (src.clone(), part.clone(), window.clone());
+  let digest = tauri::async_runtime::spawn_blocking(move || {
+    copy_with_hash(&copy_src, &copy_dst, |copied, total| {
+      let _ = w.emit("model-import-progress", serde_json::json!({ "path": copy_src, "stage": "copying", "copied": copied, "total": total }));
+    })
+  }).await.map_err(|e| e.to_string())?;
+  let digest = match digest {
+    Ok(d) => d,
+    Err(e) => { let _ = std::fs::remove_file(&part); return Err(e); }
+  };
+
+  let _ = window.emit("model-import-progress", serde_json::json!({ "path": src, "stage": "verifying", "sha256": digest }));
+  let catalog_entry = match crate::catalog::load_catalog(&app).await {
+    Ok(catalog) => catalog.models.into_iter().find(|m| m.sha256.to_lowercase() == digest),
+    Err(_) => None,
+  };
+  let verified = match (&expected, &catalog_entry) {
+    (Some(exp), _) => exp == &digest,
+    (None, Some(_)) => true,
+    (None, None) => false,
+  };
+  if !verified {
+    let _ = std::fs::remove_file(&part);
+    return Err(match expected {
+      Some(exp) => format!("sha256 mismatch: got {}, expected {}", digest, exp),
+      None => "modelo no verificado: indique el sha256 o importe un modelo del catálogo".to_string(),
+    });
+  }
+
+  let target_name = catalog_entry.as_ref().map(|m| m.file_name.clone()).unwrap_or(file_name);
+  let target = models_dir.join(&target_name);
+  if let Err(e) = downloads::install_part(&part, &target, &digest) {
+    let _ = std::fs::remove_file(&part);
+    return Err(e);
+  }
+  let target_str = target.to_string_lossy().into_owned();
+  storage::touch_model(&app, &target);
+
+  // Registrar en Ollama con el mismo flujo que los modelos descargados
+  let tag = tag
+    .or_else(|| catalog_entry.as_ref().map(|m| m.ollama_tag.clone()))
+    .unwrap_or_else(|| tag_from_file_name(&target));
+  let _ = window.emit("model-import-progress", serde_json::json!({ "path": src, "stage": "registering", "tag": tag }));
+  crate::ensure_ollama_model_available(app.clone(), tag.clone(), Some(target_str.clone())).await?;
+  let _ = window.emit("model-import-progress", serde_json::json!({ "path": src, "stage": "completed", "target": target_str }));
+
+  Ok(ImportedModel { path: target_str, sha256: digest, tag, catalog_id: catalog_entry.map(|m| m.id) })
+}
+
+// Copia un modelo instalado a `dest_dir` (p. ej. una USB) junto con su
+// `.sha256`, de modo que otro equipo pueda importarlo verificado
+#[tauri::command]
+pub async fn export_model_to_path(model_path: String, dest_dir: String, window: tauri::Window) -> Result<String, String> {
+  let src = PathBuf::from(&model_path);
+  if !src.is_file() { return Err(format!("model file not found: {}", model_path)); }
+  let dest_dir = PathBuf::from(dest_dir);
+  std::fs::create_dir_all(&dest_dir).map_err(|e| format!("cannot create {}: {}", dest_dir.display(), e))?;
+  let file_name = src.file_name().ok_or("invalid model path")?.to_os_string();
+  let target = dest_dir.join(&file_name);
+  let mut part_name = file_name.clone();
+  part_name.push(".part");
+  let part = dest_dir.join(part_name);
+  storage::ensure_free_space(&dest_dir, std::fs::metadata(&src).map_err(|e| e.to_string())?.len())?;
+
+  let known = downloads::read_digest_sidecar(&src);
+  let (copy_src, copy_dst, w) = (src.clone(), part.clone(), window.clone());
+  let digest = tauri::async_runtime::spawn_blocking(move || {
+    copy_with_hash(&copy_src, &copy_dst, |copied, total| {
+      let _ = w.emit("model-export-progress", serde_json::json!({ "path": copy_src, "stage": "copying", "copied": copied, "total": total }));
+    })
+  }).await.map_err(|e| e.to_string())?;
+  let digest = match digest {
+    Ok(d) => d,
+    Err(e) => { let _ = std::fs::remove_file(&part); return Err(e); }
+  };
+  // Si el modelo local ya tenía hash conocido, la copia debe coincidir
+  if let Some(known) = known {
+    if known != digest {
+      let _ = std::fs::remove_file(&part);
+      return Err(format!("sha256 mismatch copying to {}: got {}, expected {}", dest_dir.display(), digest, known));
+    }
+  }
+  std::fs::rename(&part, &target).map_err(|e| e.to_string())?;
+  std::fs::write(downloads::digest_sidecar(&target), format!("{}  {}\n", digest, file_name.to_string_lossy()))
+    .map_err(|e| e.to_string())?;
+  let _ = window.emit("model-export-progress", serde_json::json!({ "path": src, "stage": "completed", "target": target, "sha256": digest }));
+  Ok(target.to_string_lossy().into_owned())
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use tauri::Manager;
+
+use crate::downloads;
+use crate::models::format_gb;
+use crate::settings;
+
+// Margen que se deja libre en disco además de lo que ocupa la escritura
+const DISK_MARGIN: u64 = 512 * 1024 * 1024;
+
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct StorageReport {
+  pub models: u64,
+  pub ollama_store: u64,
+  pub downloads: u64,
+  pub logs: u64,
+  pub binaries: u64,
+  pub other: u64,
+  pub total: u64,
+  pub quota: Option<u64>,
+  pub free_disk: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct EvictionCandidate {
+  pub path: String,
+  pub file_name: String,
+  pub size: u64,
+  pub sha256: Option<String>,
+  // Segundos UNIX del último uso (o de la instalación si nunca se usó)
+  pub last_used: u64,
+}
+
+// Último uso de cada modelo (nombre de archivo -> segundos UNIX) en
+// app_data/model-usage.json; alimenta el orden LRU de la limpieza
+static USAGE_LOCK: Lazy<std::sync::Mutex<()>> = Lazy::new(|| std::sync::Mutex::new(()));
+
+fn now_secs() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn usage_path(app: &tauri::AppHandle) -> Option<PathBuf> {
+  crate::app_data_dir(app).map(|d| d.join("model-usage.json"))
+}
+
+fn read_usage(app: &tauri::AppHandle) -> HashMap<String, u64> {
+  usage_path(app)
+    .and_then(|p| std::fs::read(p).ok())
+    .and_then(|b| serde_json::from_slice(&b).ok())
+    .unwrap_or_default()
+}
+
+// Marca un modelo como usado ahora (carga en Ollama/llama-server, instalación)
+pub fn touch_model(app: &tauri::AppHandle, model: &Path) {
+  let Some(name) = model.file_name().map(|n| n.to_string_lossy().into_owned()) else { return };
+  let Some(path) = usage_path(app) else { return };
+  let _guard = USAGE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut usage = read_usage(app);
+  usage.insert(name, now_secs());
+  if let Ok(bytes) = serde_json::to_vec_pretty(&usage) {
+    let tmp = path.with_extension("json.tmp");
+    if std::fs::write(&tmp, bytes).is_ok() { let _ = std::fs::rename(&tmp, &path); }
+  }
+}
+
+// Tamaño total de un directorio sin seguir symlinks
+pub fn dir_size(path: &Path) -> u64 {
+  let mut total = 0;
+  if let Ok(rd) = std::fs::read_dir(path) {
+    for entry in rd.flatten() {
+      let Ok(meta) = entry.path().symlink_metadata() else { continue };
+      if meta.is_dir() { total += dir_size(&entry.path()); } else { total += meta.len(); }
+    }
+  }
+  total
+}
+
+// Espacio libre del volumen que contendrá `dir` (puede no existir todavía)
+pub fn free_space(dir: &Path) -> u64 {
+  let mut probe = dir;
+  while !probe.exists() {
+    match probe.parent() { Some(p) => probe = p, None => break }
+  }
+  fs2::available_space(probe).unwrap_or(u64::MAX)
+}
+
+pub fn report(app: &tauri::AppHandle) -> Result<StorageReport, String> {
+  let data_dir = crate::app_data_dir(app).ok_or("app_data_dir not found")?;
+  let mut r = StorageReport {
+    models: dir_size(&data_dir.join("models")),
+    ollama_store: dir_size(&data_dir.join("ollama-store")),
+    downloads: dir_size(&data_dir.join("downloads")),
+    logs: dir_size(&data_dir.join("logs")),
+    binaries: dir_size(&data_dir.join("bin")),
+    quota: settings::load(app).storage_quota,
+    free_disk: free_space(&data_dir),
+    ..Default::default()
+  };
+  r.total = dir_size(&data_dir);
+  r.other = r.total.saturating_sub(r.models + r.ollama_store + r.downloads + r.logs + r.binaries);
+  Ok(r)
+}
+
+// Solo comprueba el disco; para destinos fuera de app_data (p. ej. una USB)
+pub fn ensure_free_space(dir: &Path, needed: u64) -> Result<(), String> {
+  let free = free_space(dir);
+  if needed.saturating_add(DISK_MARGIN) > free {
+    return Err(format!("not enough disk space in {}: need {}, {} free", dir.display(), format_gb(needed), format_gb(free)));
+  }
+  Ok(())
+}
+
+// Comprueba disco y cuota antes de escribir `needed` bytes en `dir` dentro de
+// app_data. Si no cabe, avisa a la UI con los modelos que podrían liberarse.
+pub fn preflight(app: &tauri::AppHandle, dir: &Path, needed: u64) -> Result<(), String> {
+  let mut result = ensure_free_space(dir, needed);
+  if result.is_ok() {
+    if let Some(quota) = settings::load(app).storage_quota {
+      let used = crate::app_data_dir(app).map(|d| dir_size(&d)).unwrap_or(0);
+      if used.saturating_add(needed) > quota {
+        result = Err(format!("storage quota exceeded: {} used + {} needed > {} quota", format_gb(used), format_gb(needed), format_gb(quota)));
+      }
+    }
+  }
+  if let Err(e) = &result {
+    let candidates = eviction_candidates(app, Some(needed)).unwrap_or_default();
+    let _ = app.emit_all("storage-space-required", serde_json::json!({
+      "needed": needed,
+      "error": e,
+      "candidates": candidates,
+    }));
+  }
+  result
+}
+
+// Modelos de app_data/models del menos al más recientemente usado. El modelo
+// fijado nunca se propone. Con `needed`, solo el prefijo LRU que lo libera.
+pub fn eviction_candidates(app: &tauri::AppHandle, needed: Option<u64>) -> Result<Vec<EvictionCandidate>, String> {
+  let models_dir = crate::app_data_dir(app).ok_or("app_data_dir not found")?.join("models");
+  let pinned = settings::load(app).pinned_model.map(PathBuf::from);
+  let usage = read_usage(app);
+  let mut candidates: Vec<EvictionCandidate> = std::fs::read_dir(&models_dir).map(|rd| {
+    rd.flatten().filter_map(|entry| {
+      let path = entry.path();
+      if path.extension().and_then(|s| s.to_str()).unwrap_or("") != "gguf" { return None; }
+      if pinned.as_deref() == Some(path.as_path()) { return None; }
+      let meta = entry.metadata().ok()?;
+      let file_name = path.file_name()?.to_string_lossy().into_owned();
+      let installed = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs()).unwrap_or(0);
+      Some(EvictionCandidate {
+        last_used: usage.get(&file_name).copied().unwrap_or(installed),
+        sha256: downloads::read_digest_sidecar(&path),
+        path: path.to_string_lossy().into_owned(),
+        file_name,
+        size: meta.len(),
+      })
+    }).collect()
+  }).unwrap_or_default();
+  candidates.sort_by_key(|c| c.last_used);
+  if let Some(needed) = needed {
+    let mut freed = 0u64;
+    candidates.retain(|c| {
+      if freed >= needed { return false; }
+      // El blob importado en Ollama es una copia del GGUF: se libera el doble
+      freed += if c.sha256.is_some() { c.size * 2 } else { c.size };
+      true
+    });
+  }
+  Ok(candidates)
+}
+
+// Manifiestos de ollama-store que referencian el blob `sha256:<hex>`, con el
+// nombre del modelo tal como lo espera la API de Ollama
+fn ollama_manifests_for(store: &Path, sha256: &str) -> Vec<(PathBuf, String)> {
+  fn walk(dir: &Path, root: &Path, digest: &str, out: &mut Vec<(PathBuf, String)>) {
+    let Ok(rd) = std::fs::read_dir(dir) else { return };
+    for entry in rd.flatten() {
+      let path = entry.path();
+      if path.is_dir() { walk(&path, root, digest, out); continue; }
+      let Ok(text) = std::fs::read_to_string(&path) else { continue };
+      if !text.contains(digest) { continue; }
+      // manifests/<host>/<namespace>/<modelo>/<tag>
+      let parts: Vec<String> = path.strip_prefix(root).map(|rel| rel.iter().map(|c| c.to_string_lossy().into_owned()).collect()).unwrap_or_default();
+      if let [host, namespace, model, tag] = parts.as_slice() {
+        let name = if host == "registry.ollama.ai" && namespace == "library" {
+          format!("{}:{}", model, tag)
+        } else {
+          format!("{}/{}/{}:{}", host, namespace, model, tag)
+        };
+        out.push((path.clone(), name));
+      }
+    }
+  }
+  let root = store.join("manifests");
+  let mut out = Vec::new();
+  walk(&root, &root, &format!("sha256:{}", sha256), &mut out);
+  out
+}
+
+// Quita de Ollama los tags que usan el GGUF y, si quedó huérfano, su blob
+pub async fn evict_from_ollama(store: &Path, sha256: &str) {
+  let ollama = crate::ollama::OllamaClient::from_state().ok();
+  for (manifest, name) in ollama_manifests_for(store, sha256) {
+    let deleted = match &ollama {
+      Some(client) => client.delete(&name).await.is_ok(),
+      None => false,
+    };
+    // Ollama apagado: borrar el manifiesto a mano
+    if !deleted { let _ = std::fs::remove_file(&manifest); }
+  }
+  let blob = store.join("blobs").join(format!("sha256-{}", sha256));
+  if blob.exists() && ollama_manifests_for(store, sha256).is_empty() {
+    let _ = std::fs::remove_file(&blob);
+  }
+}
+
+#[tauri::command]
+pub fn storage_report(app: tauri::AppHandle) -> Result<StorageReport, String> {
+  report(&app)
+}
+
+#[tauri::command]
+pub fn set_storage_quota(quota_bytes: Option<u64>, app: tauri::AppHandle) -> Result<StorageReport, String> {
+  settings::update(&app, |s| s.storage_quota = quota_bytes)?;
+  report(&app)
+}
+
+#[tauri::command]
+pub fn list_eviction_candidates(needed_bytes: Option<u64>, app: tauri::AppHandle) -> Result<Vec<EvictionCandidate>, String> {
+  eviction_candidates(&app, needed_bytes)
+}
+
+// Borra los modelos que el usuario confirmó en la UI (la lista explícita es la
+// confirmación). Solo acepta modelos propuestos por eviction_candidates.
+#[tauri::command]
+pub async fn evict_models(paths: Vec<String>, app: tauri::AppHandle) -> Result<u64, String> {
+  let data_dir = crate::app_data_dir(&app).ok_or("app_data_dir not found")?;
+  let allowed = eviction_candidates(&app, None)?;
+  let mut freed = 0u64;
+  for path in paths {
+    let candidate = allowed.iter().find(|c| c.path == path)
+      .ok_or_else(|| format!("{} is not an evictable model", path))?;
+    let model = PathBuf::from(&candidate.path);
+    std::fs::remove_file(&model).map_err(|e| format!("cannot remove {}: {}", model.display(), e))?;
+    let _ = std::fs::remove_file(downloads::digest_sidecar(&model));
+    freed += candidate.size;
+    if let Some(sha) = &candidate.sha256 {
+      let store = data_dir.join("ollama-store");
+      let before = dir_size(&store);
+      evict_from_ollama(&store, sha).await;
+      freed += before.saturating_sub(dir_size(&store));
+    }
+    crate::boot_log(&app, format!("[tauri] modelo {} eliminado para liberar espacio", candidate.file_name)).await;
+  }
+  Ok(freed)
+}
@@ -8,6 +8,7 @@ import { translations } from "@/lib/constants/translations";
 import { Button } from "@/components/ui/button";
 import { Card } from "@/components/ui/card";
 import { getAIClient, setAIClientHost } from "@/services/ai/ollama-client";
+import { getDefaultModel, getOllamaUrl } from "@/lib/tauri/endpoints";
 import {
   ArrowUp,
   Mic,
@@ -216,7 +217,6 @@ export default function AIAssistantPage() {
     process.env.NEXT_PUBLIC_MODEL_DOWNLOAD_URL ||
     "https://huggingface.co/ganado/ollama/resolve/main/DeepSeek-R1-Distill-Qwen-1.5B-Q8_0.gguf?download=true";
   const MODEL_SHA = process.env.NEXT_PUBLIC_MODEL_SHA256 || null;
-  const LLAMA_PORT = Number(process.env.NEXT_PUBLIC_LLAMA_PORT || 11434);
   const PREFERRED_MODEL =
     process.env.NEXT_PUBLIC_OLLAMA_MODEL || "deepseek-r1-qwen-1_5b:latest";
 
@@ -327,7 +327,7 @@ export default function AIAssistantPage() {
     try {
       const isTauriEnv =
         typeof window !== "undefined" && !!(window as any).__TAURI__;
-      if (isTauriEnv) setAIClientHost(`http://127.0.0.1:${LLAMA_PORT}`);
+      if (isTauriEnv) void getOllamaUrl().then(setAIClientHost);
     } catch {}
 
     (async () => {
@@ -3382,12 +3382,12 @@ export default function AIAssistantPage() {
     try {
       setIsDownloading(true);
       // Único flujo: Ollama (server + modelo)
-      await tauri.invoke("start_ollama_server", { port: LLAMA_PORT });
+      await tauri.invoke("start_ollama_server", { port: null });
       await tauri.invoke("ensure_ollama_model_available", {
-        tag: "deepseek-r1-qwen-1_5b:latest",
+        tag: await getDefaultModel(),
         modelPath: null,
       });
-      setAIClientHost(`http://127.0.0.1:${LLAMA_PORT}`);
+      void getOllamaUrl().then(setAIClientHost);
       setLocalModelAvailable(true);
       setLlamaRunning(true);
     } catch (e) {
@@ -3407,11 +3407,11 @@ export default function AIAssistantPage() {
         const ok = await aiClient.checkLocalAvailability();
         if (ok) {
           setLocalModelAvailable(true);
-          if (isTauri) setAIClientHost(`http://127.0.0.1:${LLAMA_PORT}`);
+          if (isTauri) void getOllamaUrl().then(setAIClientHost);
         }
       } catch {}
     })();
-  }, [aiClient, isTauri, LLAMA_PORT]);
+  }, [aiClient, isTauri]);
 
   // Cuando el navegador está offline, fuerza el host local para evitar proxies remotos
   useEffect(() => {
@@ -3420,7 +3420,7 @@ export default function AIAssistantPage() {
         const offline =
           typeof navigator !== "undefined" && navigator.onLine === false;
         if (offline) {
-          setAIClientHost(`http://127.0.0.1:${LLAMA_PORT}`);
+          void getOllamaUrl().then(setAIClientHost);
         }
       } catch {}
     };
@@ -3429,7 +3429,7 @@ export default function AIAssistantPage() {
       window.addEventListener("offline", applyOfflineHost);
       return () => window.removeEventListener("offline", applyOfflineHost);
     }
-  }, [LLAMA_PORT]);
+  }, []);
 
   const confirmAndExecute = async () => {
     if (!pendingAction) return;
@@ -0,0 +1,24 @@
+import { createHash } from "crypto";
+import { NextRequest, NextResponse } from "next/server";
+
+export const runtime = "nodejs";
+export const dynamic = "force-dynamic";
+
+// Handshake de identidad para la app de escritorio: Tauri lanza el servidor con
+// GANADO_INSTANCE_TOKEN y comprueba que quien escucha en el puerto es el suyo.
+// Se responde sha256(nonce:token) para no exponer el token.
+export async function GET(req: NextRequest) {
+  const token = process.env.GANADO_INSTANCE_TOKEN;
+  if (!token) {
+    return NextResponse.json({ error: "not a desktop instance" }, { status: 404 });
+  }
+  const nonce = req.nextUrl.searchParams.get("nonce") || "";
+  if (!/^[0-9a-f]{16,128}$/i.test(nonce)) {
+    return NextResponse.json({ error: "invalid nonce" }, { status: 400 });
+  }
+  const proof = createHash("sha256").update(`${nonce}:${token}`).digest("hex");
+  return NextResponse.json(
+    { app: "ganado-ai", proof },
+    { headers: { "cache-control": "no-store" } }
+  );
+}
@@ -1,9 +1,11 @@
 import { NextRequest, NextResponse } from "next/server";
+import { desktopOllamaUrl } from "@/lib/tauri/desktop-endpoints";
 
 export const runtime = "nodejs";
 
 async function proxy(req: NextRequest, ctx: { params: { path: string[] } }) {
   let base =
+    desktopOllamaUrl() ||
     process.env.NEXT_PUBLIC_OLLAMA_HOST ||
     process.env.OLLAMA_SERVER_URL ||
     "http://127.0.0.1:11434";
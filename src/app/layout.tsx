@@ -466,19 +466,24 @@ export default function RootLayout({
         new Promise(function(_, reject){ to = setTimeout(function(){ reject(new Error('timeout:'+label)); }, ms); })
       ]).finally(function(){ try{ clearTimeout(to); }catch{} });
     }
+    // Puerto real de Ollama: Tauri elige uno libre si el preferido está ocupado
+    var ollamaPort = Number(window.process?.env?.NEXT_PUBLIC_LLAMA_PORT||11434);
     try{
       setMsg('Iniciando servidor de IA local…');
       log('[BOOT] start_ollama_server (timeout 12s)');
-      await withTimeout(window.__TAURI__.invoke('start_ollama_server', { port: Number(window.process?.env?.NEXT_PUBLIC_LLAMA_PORT||11434) }), 12000, 'start_ollama_server');
-      log('[BOOT] sidecar/ollama server start solicitado');
+      var startedPort = await withTimeout(window.__TAURI__.invoke('start_ollama_server', { port: null }), 12000, 'start_ollama_server');
+      if(startedPort){ ollamaPort = Number(startedPort); }
+      log('[BOOT] sidecar/ollama server start solicitado (puerto '+ollamaPort+')');
     }catch(e){ setMsg('Intentando abrir Ollama…'); log('[BOOT][warn] start_ollama_server: '+String(e)); }
+    // Tag por defecto persistido en Tauri (set_default_model)
+    var defaultModel = 'deepseek-r1-qwen-1_5b:latest';
+    try{ defaultModel = (await window.__TAURI__.invoke('get_default_model')) || defaultModel; }catch(e){}
     try{
-      setMsg('Verificando/creando modelo DeepSeek…');
+      setMsg('Verificando/creando modelo '+defaultModel+'…');
       log('[BOOT] ensure_model (timeout 25s)');
-      // Modelo más ligero por defecto para respuesta inicial más rápida
-      await withTimeout(window.__TAURI__.invoke('ensure_ollama_model_available', { tag: 'deepseek-r1-qwen-1_5b:latest', modelPath: null }), 25000, 'ensure_model');
+      await withTimeout(window.__TAURI__.invoke('ensure_ollama_model_available', { tag: defaultModel, modelPath: null }), 25000, 'ensure_model');
       setMsg('Modelo verificado.');
-      log('[BOOT] modelo deepseek-r1-qwen-1_5b:latest verificado');
+      log('[BOOT] modelo '+defaultModel+' verificado');
     }catch(e){
       // Registrar error pero no bloquear: degradar y continuar
       log('[BOOT][error] ensure_ollama_model_available: '+(e&&e.message?e.message:String(e)));
@@ -486,13 +491,13 @@ export default function RootLayout({
       degradeAndProceed('ensure_model_failed');
       return;
     }
-    // Precalentar con consulta mínima con estrategia de host: proxy 4317 → directo 11434
+    // Precalentar con consulta mínima con estrategia de host: proxy del sidecar → Ollama directo
     async function warm(host){
       const controller = new AbortController();
       const to = setTimeout(()=>controller.abort(), 15000);
       try{
         log('[BOOT] warmup host='+host);
-        const res = await fetch(host+'/api/chat', { method:'POST', signal: controller.signal, headers:{'Content-Type':'application/json'}, body: JSON.stringify({ model: 'deepseek-r1-qwen-1_5b:latest', messages:[{role:'user', content:'ok'}], stream:false, options:{ num_predict: 16 } }) });
+        const res = await fetch(host+'/api/chat', { method:'POST', signal: controller.signal, headers:{'Content-Type':'application/json'}, body: JSON.stringify({ model: defaultModel, messages:[{role:'user', content:'ok'}], stream:false, options:{ num_predict: 16 } }) });
         clearTimeout(to);
         if(!res.ok){ throw new Error('status '+res.status); }
         const j = await res.json().catch(()=>({}));
@@ -505,8 +510,10 @@ export default function RootLayout({
       }
     }
     // Probar primero sidecar proxy, luego puerto directo
-    var sidecar = 'http://127.0.0.1:4317/api/ollama';
-    var direct = 'http://127.0.0.1:'+Number(window.process?.env?.NEXT_PUBLIC_LLAMA_PORT||11434);
+    var endpoints = {};
+    try{ endpoints = (await window.__TAURI__.invoke('get_service_endpoints')) || {}; }catch{}
+    var sidecar = ((endpoints.node && endpoints.node.url) || 'http://127.0.0.1:4317')+'/api/ollama';
+    var direct = (endpoints.ollama && endpoints.ollama.url) || ('http://127.0.0.1:'+ollamaPort);
     var ok = false;
     // pequeño ping a /api/tags para elegir host rápidamente
     async function ping(base){
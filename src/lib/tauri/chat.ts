@@ -0,0 +1,99 @@
+// Chat en streaming por eventos de Tauri (ai_chat_stream / ai-token). Habla
+// directo con Ollama o llama-server, así que sigue funcionando aunque el
+// sidecar Node esté caído.
+
+import { isTauri } from "./endpoints";
+
+export type NativeChatMessage = { role: string; content: string };
+
+export type NativeChatResult = {
+  requestId: string;
+  backend: "ollama" | "llama";
+  model: string;
+  content: string;
+  cancelled: boolean;
+  evalCount?: number | null;
+};
+
+export type QueueEvent = {
+  requestId: string;
+  backend: "ollama" | "llama";
+  state: "queued" | "running" | "rejected" | "timeout" | "cancelled";
+  position?: number | null;
+  queued: number;
+};
+
+type TokenEvent = {
+  requestId: string;
+  token: string;
+  thinking?: string | null;
+  done: boolean;
+  cancelled?: boolean;
+  error?: string | null;
+};
+
+export function canUseNativeChat(): boolean {
+  const tauri = isTauri() ? (window as any).__TAURI__ : null;
+  return Boolean(tauri?.invoke && tauri?.event?.listen);
+}
+
+export async function nativeChatStream(opts: {
+  messages: NativeChatMessage[];
+  model?: string;
+  options?: Record<string, unknown>;
+  // "background" cede el turno al chat interactivo en la cola de inferencia
+  priority?: "interactive" | "background";
+  signal?: AbortSignal;
+  onToken?: (token: string, accumulated: string) => void;
+  onQueue?: (event: QueueEvent) => void;
+}): Promise<NativeChatResult> {
+  const tauri = (window as any).__TAURI__;
+  const requestId =
+    typeof crypto !== "undefined" && "randomUUID" in crypto
+      ? crypto.randomUUID()
+      : `${Date.now()}-${Math.random().toString(16).slice(2)}`;
+  let accumulated = "";
+  const unlisten: () => void = await tauri.event.listen(
+    "ai-token",
+    (ev: { payload: TokenEvent }) => {
+      const p = ev?.payload;
+      if (!p || p.requestId !== requestId || p.done || !p.token) return;
+      accumulated += p.token;
+      try {
+        opts.onToken?.(p.token, accumulated);
+      } catch {}
+    }
+  );
+  const unlistenQueue: () => void = await tauri.event.listen(
+    "ai-queue",
+    (ev: { payload: QueueEvent }) => {
+      if (ev?.payload?.requestId !== requestId) return;
+      try {
+        opts.onQueue?.(ev.payload);
+      } catch {}
+    }
+  );
+  const onAbort = () => {
+    void tauri.invoke("ai_cancel", { requestId }).catch(() => {});
+  };
+  if (opts.signal?.aborted) {
+    unlisten();
+    unlistenQueue();
+    throw new Error("ABORTED_BY_USER");
+  }
+  opts.signal?.addEventListener("abort", onAbort, { once: true });
+  try {
+    return (await tauri.invoke("ai_chat_stream", {
+      requestId,
+      messages: opts.messages,
+      model: opts.model ?? null,
+      backend: null,
+      options: opts.options ?? null,
+      priority: opts.priority ?? null,
+    })) as NativeChatResult;
+  } finally {
+    opts.signal?.removeEventListener("abort", onAbort);
+    unlisten();
+    unlistenQueue();
+  }
+}
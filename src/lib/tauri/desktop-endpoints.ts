@@ -0,0 +1,14 @@
+import { readFileSync } from "fs";
+
+// Solo servidor (Node): en la app de escritorio Tauri escribe el puerto real de
+// Ollama en GANADO_ENDPOINTS_FILE (puede no ser 11434 si estaba ocupado)
+export function desktopOllamaUrl(): string | null {
+  const file = process.env.GANADO_ENDPOINTS_FILE;
+  if (!file) return null;
+  try {
+    const endpoints = JSON.parse(readFileSync(file, "utf8"));
+    return typeof endpoints?.ollama?.url === "string" ? endpoints.ollama.url : null;
+  } catch {
+    return null;
+  }
+}
@@ -0,0 +1,71 @@
+// Puertos reales de los servicios locales de la app de escritorio. Tauri elige
+// un puerto libre si el preferido está ocupado, así que no hay que asumir
+// 127.0.0.1:4317 (Next) ni 11434 (Ollama).
+
+export type ServiceName = "node" | "ollama" | "llama" | "gateway";
+
+export type ServiceEndpoint = {
+  port: number;
+  url: string;
+  owned: boolean;
+};
+
+export type ServiceEndpoints = Partial<Record<ServiceName, ServiceEndpoint>>;
+
+const DEFAULT_NODE_URL = "http://127.0.0.1:4317";
+const DEFAULT_OLLAMA_URL = `http://127.0.0.1:${
+  process.env.NEXT_PUBLIC_LLAMA_PORT || 11434
+}`;
+
+export function isTauri(): boolean {
+  return typeof window !== "undefined" && Boolean((window as any).__TAURI__);
+}
+
+export async function getServiceEndpoints(): Promise<ServiceEndpoints> {
+  if (!isTauri()) return {};
+  try {
+    const invoke = (window as any).__TAURI__?.invoke;
+    if (typeof invoke !== "function") return {};
+    return ((await invoke("get_service_endpoints")) || {}) as ServiceEndpoints;
+  } catch {
+    return {};
+  }
+}
+
+// URL del servidor Next local. Si la página ya se sirve desde él, su origen es
+// la respuesta correcta aunque Tauri aún no haya registrado el endpoint.
+export async function getLocalServerUrl(): Promise<string> {
+  const endpoints = await getServiceEndpoints();
+  if (endpoints.node?.url) return endpoints.node.url;
+  if (typeof window !== "undefined" && /^http:\/\/127\.0\.0\.1:\d+$/.test(window.location.origin)) {
+    return window.location.origin;
+  }
+  return DEFAULT_NODE_URL;
+}
+
+export async function getOllamaUrl(): Promise<string> {
+  const endpoints = await getServiceEndpoints();
+  return endpoints.ollama?.url || DEFAULT_OLLAMA_URL;
+}
+
+// Base OpenAI-compatible (/v1) que reparte entre Ollama y llama-server; null
+// fuera de Tauri o si el gateway no arrancó
+export async function getGatewayUrl(): Promise<string | null> {
+  const endpoints = await getServiceEndpoints();
+  return endpoints.gateway?.url ? `${endpoints.gateway.url}/v1` : null;
+}
+
+const DEFAULT_MODEL =
+  process.env.NEXT_PUBLIC_OLLAMA_MODEL || "deepseek-r1-qwen-1_5b:latest";
+
+// Tag de Ollama por defecto elegido en la app de escritorio (set_default_model)
+export async function getDefaultModel(): Promise<string> {
+  if (!isTauri()) return DEFAULT_MODEL;
+  try {
+    const invoke = (window as any).__TAURI__?.invoke;
+    if (typeof invoke !== "function") return DEFAULT_MODEL;
+    return ((await invoke("get_default_model")) as string) || DEFAULT_MODEL;
+  } catch {
+    return DEFAULT_MODEL;
+  }
+}
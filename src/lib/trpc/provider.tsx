@@ -6,6 +6,7 @@ import { useEffect, useMemo, useState } from "react";
 import { trpc } from "./client";
 import superjson from "superjson";
 import { useAuth } from "@clerk/nextjs";
+import { getLocalServerUrl } from "@/lib/tauri/endpoints";
 
 async function probe(url: string, timeoutMs = 1200): Promise<boolean> {
   try {
@@ -49,9 +50,10 @@ export function TRPCProvider({ children }: { children: React.ReactNode }) {
       const isTauri =
         typeof window !== "undefined" && (window as any).__TAURI__;
       if (isTauri) {
-        const localUrl = "http://127.0.0.1:4317/api/trpc";
+        const localServer = await getLocalServerUrl();
+        const localUrl = `${localServer}/api/trpc`;
         // Preferir servidor local embebido si está listo; evitar CORS con remoto
-        const localReady = await probe("http://127.0.0.1:4317/manifest.webmanifest", 1800);
+        const localReady = await probe(`${localServer}/manifest.webmanifest`, 1800);
         if (localReady) {
           if (!cancelled) setResolvedUrl(localUrl);
           return;
@@ -3,6 +3,7 @@ import { createTRPCRouter, protectedProcedure, publicProcedure } from "../trpc";
 import { prisma } from "@/lib/prisma";
 import { getAIClient } from "@/services/ai/ollama-client";
 import { aiModuleSpecs } from "@/modules/ai-specs";
+import { desktopOllamaUrl } from "@/lib/tauri/desktop-endpoints";
 
 // -------- Helpers --------
 function tokenize(text: string): string[] {
@@ -170,6 +171,7 @@ export const aiRouter = createTRPCRouter({
   // ---------- Infra endpoints previos (checkLocalModel, checkCloudAvailable, ensureLocalModel) ----------
   checkLocalModel: publicProcedure.mutation(async () => {
     const base =
+      desktopOllamaUrl() ||
       process.env.NEXT_PUBLIC_OLLAMA_HOST ||
       process.env.OLLAMA_SERVER_URL ||
       "http://127.0.0.1:11434";
@@ -195,6 +197,7 @@ export const aiRouter = createTRPCRouter({
     .input(z.object({ model: z.string().default("deepseek-r1:latest") }))
     .mutation(async ({ input }) => {
       const base =
+        desktopOllamaUrl() ||
         process.env.NEXT_PUBLIC_OLLAMA_HOST ||
         process.env.OLLAMA_SERVER_URL ||
         "http://127.0.0.1:11434";
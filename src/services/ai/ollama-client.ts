@@ -1,6 +1,8 @@
 import { translations } from "@/lib/constants/translations";
 import { aiModuleSpecs } from "@/modules/ai-specs";
 import { z } from "zod";
+import { getDefaultModel, getLocalServerUrl, getOllamaUrl, isTauri as isTauriApp } from "@/lib/tauri/endpoints";
+import { canUseNativeChat, nativeChatStream } from "@/lib/tauri/chat";
 
 // AI Client configuration
 interface AIResponse {
@@ -39,10 +41,15 @@ export class AIClient {
         ? "http://127.0.0.1:11434"
         : "/api/ollama"; // proxy solo en web prod
 
-    // En Tauri: preferir SIEMPRE el proxy interno del servidor Node sidecar (evita CORS)
-    // Puerto del sidecar (ver TRPCProvider): 4317
+    // En Tauri: preferir SIEMPRE el proxy interno del servidor Node sidecar (evita CORS).
+    // El puerto lo elige Tauri; si la página ya se sirve desde el sidecar se usa su
+    // origen y checkLocalAvailability lo corrige con get_service_endpoints.
     if (isTauri) {
-      const tauriProxy = "http://127.0.0.1:4317/api/ollama";
+      const origin = window.location.origin;
+      const sidecar = /^http:\/\/127\.0\.0\.1:\d+$/.test(origin)
+        ? origin
+        : "http://127.0.0.1:4317";
+      const tauriProxy = `${sidecar}/api/ollama`;
       this._ollamaHost = (lsHost || tauriProxy).replace(/\/$/, "");
     } else {
       this._ollamaHost = (
@@ -85,8 +92,7 @@ export class AIClient {
   }
 
   async checkLocalAvailability(): Promise<boolean> {
-    const preferred =
-      process.env.NEXT_PUBLIC_OLLAMA_MODEL || "deepseek-r1-qwen-1_5b:latest";
+    const preferred = await getDefaultModel();
 
     const tryCheck = async (base: string) => {
       const controller = new AbortController();
@@ -120,13 +126,16 @@ export class AIClient {
       if (ok) return true;
     } catch {}
 
-    // 2) En Tauri: intentar puerto directo de Ollama si el proxy no está disponible
+    // 2) En Tauri: proxy del sidecar en su puerto real y, si no está disponible,
+    // el puerto directo de Ollama que reporta get_service_endpoints
     try {
-      const isBrowser = typeof window !== "undefined";
-      const isTauri = isBrowser && !!(window as any).__TAURI__;
-      if (isTauri) {
-        const port = Number(process.env.NEXT_PUBLIC_LLAMA_PORT || 11434);
-        const direct = `http://127.0.0.1:${port}`;
+      if (isTauriApp()) {
+        const proxy = `${await getLocalServerUrl()}/api/ollama`;
+        if (proxy !== this._ollamaHost && (await tryCheck(proxy))) {
+          this.setHost(proxy);
+          return true;
+        }
+        const direct = await getOllamaUrl();
         const ok = await tryCheck(direct);
         if (ok) {
           this.setHost(direct);
@@ -253,6 +262,46 @@ export class AIClient {
           this._ollamaHost
         } model=${this.model}`
       );
+      // En Tauri: chat nativo por eventos, sin depender del sidecar Node
+      if (canUseNativeChat()) {
+        // Si la petición llegó a la cola de inferencia, un fallo (cola llena,
+        // timeout) no se reintenta por HTTP para no saltarse el planificador
+        let reachedQueue = false;
+        try {
+          emitLog(`[LOCAL] native:ai_chat_stream model=${this.model}`);
+          const result = await nativeChatStream({
+            messages: [
+              { role: "system", content: systemPrompt },
+              { role: "user", content: userPrompt },
+            ],
+            model: this.model,
+            options: { num_predict: 256, temperature: 0.2 },
+            signal: (context as any)?.signal,
+            onQueue: (q) => {
+              reachedQueue = true;
+              if (q.state === "queued")
+                emitLog(`[LOCAL] native:queued position=${q.position}/${q.queued}`);
+            },
+            onToken: (_token, accumulated) => {
+              try {
+                if (typeof (context as any)?.onPartial === "function") {
+                  (context as any).onPartial(accumulated);
+                }
+              } catch {}
+            },
+          });
+          if (result.cancelled) throw new Error("ABORTED_BY_USER");
+          emitLog(
+            `[LOCAL] native:done backend=${result.backend} len=${result.content.length}`
+          );
+          return this.parseAIResponse(result.content.trim());
+        } catch (e: any) {
+          if (e?.message === "ABORTED_BY_USER") throw e;
+          if (reachedQueue) throw new Error(String(e?.message || e));
+          emitLog(`[LOCAL] native:fail ${e?.message || e}; usando HTTP`);
+        }
+      }
+
       const sendChat = async (host: string, abortFirstByteMs: number) => {
         const controller = new AbortController();
         const externalSignal: AbortSignal | undefined = (context as any)
@@ -464,10 +513,9 @@ export class AIClient {
           await warmup(this._ollamaHost);
           return await sendChat(this._ollamaHost, 30_000);
         }
-        // Si el proxy 4317 falla, probar directo 11434 como fallback rápido en Tauri
+        // Si el proxy del sidecar falla, probar Ollama directo como fallback rápido en Tauri
         try {
-          const port = Number(process.env.NEXT_PUBLIC_LLAMA_PORT || 11434);
-          const direct = `http://127.0.0.1:${port}`;
+          const direct = await getOllamaUrl();
           if (this._ollamaHost !== direct) {
             emitLog(`[LOCAL] fallback:direct ${direct}`);
             await warmup(direct);